@@ -0,0 +1,146 @@
+//! Describing binary values, such as keystores, that can't be shown as text
+//!
+//! Values are recognized by their leading magic bytes, like `file` does, so
+//! nothing needs to be extracted or decrypted to tell what they are.
+
+use std::fmt::Write;
+
+use sha2::{Digest, Sha256};
+use x509_parser::der_parser::der::parse_der;
+
+/// How many bytes `--hexdump` shows of each value
+pub const HEXDUMP_BYTES: usize = 256;
+
+/// Formats recognized by a fixed prefix
+const MAGIC: &[(&[u8], &str)] = &[
+    (b"\xfe\xed\xfe\xed", "Java keystore (JKS)"),
+    (b"\xce\xce\xce\xce", "Java keystore (JCEKS)"),
+    (b"\x1f\x8b", "gzip compressed data"),
+    (b"BZh", "bzip2 compressed data"),
+    (b"\xfd7zXZ\x00", "xz compressed data"),
+    (b"\x28\xb5\x2f\xfd", "zstd compressed data"),
+    (b"PK\x03\x04", "zip archive"),
+    (b"\x05\x02", "Kerberos keytab"),
+    (b"\x7fELF", "ELF executable"),
+    (b"\x89PNG", "PNG image"),
+    (b"\xff\xd8\xff", "JPEG image"),
+    (b"%PDF", "PDF document"),
+];
+
+/// Name the format of a binary value
+pub fn kind(value: &[u8]) -> String {
+    if let Some((_, name)) = MAGIC.iter().find(|(magic, _)| value.starts_with(magic)) {
+        return name.to_string();
+    }
+    if value.first() == Some(&0x30) {
+        if let Ok((_, cert)) = x509_parser::parse_x509_certificate(value) {
+            return format!("DER certificate {}", cert.subject());
+        }
+        // PFX is a SEQUENCE starting with version 3, often BER encoded with
+        // an indefinite length by Java
+        if sequence_contents(value).is_some_and(|c| c.starts_with(&[0x02, 0x01, 0x03])) {
+            return "PKCS#12 keystore".to_string();
+        }
+        if parse_der(value).is_ok_and(|(rest, _)| rest.is_empty()) {
+            return "DER encoded ASN.1".to_string();
+        }
+    }
+    "unrecognized binary data".to_string()
+}
+
+/// The contents of an ASN.1 SEQUENCE, after its tag and length
+fn sequence_contents(value: &[u8]) -> Option<&[u8]> {
+    let length = *value.get(1)?;
+    let header = match length {
+        0x00..=0x80 => 2,
+        0x81..=0x84 => 2 + usize::from(length & 0x7f),
+        _ => return None,
+    };
+    value.get(header..)
+}
+
+/// Hex encoded SHA-256 of a value, to compare against a file with `sha256sum`
+pub fn sha256(value: &[u8]) -> String {
+    Sha256::digest(value)
+        .iter()
+        .fold(String::new(), |mut out, byte| {
+            let _ = write!(out, "{:02x}", byte);
+            out
+        })
+}
+
+/// Canonical hex and ASCII lines of the first `limit` bytes, like
+/// `hexdump -C`
+pub fn hexdump(value: &[u8], limit: usize) -> Vec<String> {
+    let mut lines: Vec<String> = value[..limit.min(value.len())]
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut hex = String::new();
+            for (j, byte) in chunk.iter().enumerate() {
+                let gap = if j == 8 { "  " } else { " " };
+                let _ = write!(hex, "{}{:02x}", gap, byte);
+            }
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:08x} {:49}  |{}|", i * 16, hex, ascii)
+        })
+        .collect();
+    if value.len() > limit {
+        lines.push(format!("... {} more bytes", value.len() - limit));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DER: &[u8] = include_bytes!("../tests/fixtures/tls/rsa.der");
+    const PKCS12: &[u8] = include_bytes!("../tests/fixtures/tls/rsa.p12");
+    const JKS: &[u8] = include_bytes!("../tests/fixtures/tls/rsa.jks");
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(kind(JKS), "Java keystore (JKS)");
+        assert_eq!(kind(PKCS12), "PKCS#12 keystore");
+        assert_eq!(kind(DER), "DER certificate CN=example.com, O=Acme");
+        assert_eq!(kind(b"\x1f\x8b\x08\x00\x00"), "gzip compressed data");
+        assert_eq!(kind(b"\x05\x02\x00\x00\x00\x3c"), "Kerberos keytab");
+        // PKCS#12 with an indefinite length, as written by Java
+        assert_eq!(kind(b"\x30\x80\x02\x01\x03\x30\x80"), "PKCS#12 keystore");
+        assert_eq!(kind(b"\x30\x03\x02\x01\x00"), "DER encoded ASN.1");
+        assert_eq!(kind(b"\x30\x82"), "unrecognized binary data");
+        assert_eq!(kind(b"\x00\x01\x02"), "unrecognized binary data");
+    }
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha256(b"hunter2"),
+            "f52fbd32b2b3b86ff88ef6c490628285f482af15ddcb29541f94bcf526a3f6c7"
+        );
+    }
+
+    #[test]
+    fn test_hexdump() {
+        assert_eq!(
+            hexdump(&JKS[..20], 256),
+            [
+                "00000000  fe ed fe ed 00 00 00 02  00 00 00 01 00 00 00 01  |................|",
+                "00000010  00 01 31 00                                       |..1.|",
+            ]
+        );
+        let lines = hexdump(JKS, 32);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2], format!("... {} more bytes", JKS.len() - 32));
+    }
+}
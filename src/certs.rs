@@ -0,0 +1,285 @@
+//! Reporting on the certificates stored in secrets, soonest to expire first
+
+use std::fmt::Write;
+use std::process::ExitCode;
+
+use clap::{Args, ValueEnum};
+use colorful::Colorful;
+use k8s_openapi::api::core::v1::Secret;
+use kube::Client;
+use serde::Serialize;
+
+use crate::filter::Filter;
+use crate::{Config, output, tls};
+
+#[derive(Args, Debug)]
+pub struct CertsArgs {
+    /// Namespace to look in, or a comma separated list of namespaces and glob
+    /// patterns
+    #[clap(required_unless_present = "all_namespaces")]
+    namespace: Option<String>,
+
+    /// Only check secrets whose name contains this
+    query: Option<String>,
+
+    /// Check secrets across every namespace
+    #[clap(short = 'A', long)]
+    all_namespaces: bool,
+
+    /// Exit with 1 when a certificate expires within this many days
+    #[clap(long, value_name = "DAYS", default_value_t = 30)]
+    warn_days: i64,
+
+    /// Output format
+    #[clap(short = 'o', long, value_enum, default_value_t)]
+    output: CertsFormat,
+}
+
+/// Formats the certificate report can be rendered in
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CertsFormat {
+    /// A table meant for humans
+    #[default]
+    Text,
+    /// A JSON array with one object per certificate, for alerting
+    Json,
+}
+
+/// A certificate along with where it was found
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    namespace: String,
+    secret: String,
+    key: String,
+    subject: String,
+    sans: Vec<String>,
+    issuer: String,
+    serial: String,
+    not_before: String,
+    not_after: String,
+    days_until_expiry: i64,
+    expiring: bool,
+    #[serde(skip)]
+    expires_at: i64,
+}
+
+/// Scan the secrets of every type for certificates and report them, exiting
+/// with 1 when any expire within `--warn-days`. Errors, including a namespace
+/// that doesn't exist, exit with 2 from `main` so they can't be mistaken for
+/// an expiring certificate.
+pub async fn run(client: Client, args: &CertsArgs) -> anyhow::Result<ExitCode> {
+    let mut config = Config {
+        namespace: args.namespace.clone(),
+        query: args.query.clone(),
+        all_namespaces: args.all_namespaces,
+        show_all: true,
+        ..Default::default()
+    };
+    config.shift_query()?;
+    let filter = Filter::new(&config)?;
+    let secrets = crate::select_secrets(&config, &filter, client.clone()).await?;
+    if secrets.is_empty() && !config.spans_namespaces() {
+        let namespace = config.namespace.as_deref().unwrap_or_default();
+        crate::ensure_namespace(client, namespace).await?;
+    }
+
+    let entries = entries(&secrets, output::now(), args.warn_days);
+    match args.output {
+        CertsFormat::Text if entries.is_empty() => println!("No certificates found"),
+        CertsFormat::Text => print!("{}", table(&entries, args.warn_days)),
+        CertsFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+    }
+
+    if entries.iter().any(|e| e.expiring) {
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Every certificate in the secrets, sorted by expiry
+fn entries(secrets: &[Secret], now: i64, warn_days: i64) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    for s in secrets {
+        for (key, value) in s.data.iter().flatten() {
+            let Some(bundle) = tls::parse(&value.0) else {
+                continue;
+            };
+            for cert in bundle.certificates {
+                let days = cert.days_until_expiry(now);
+                entries.push(Entry {
+                    namespace: s.metadata.namespace.clone().unwrap_or_default(),
+                    secret: s.metadata.name.clone().unwrap_or_default(),
+                    key: key.clone(),
+                    not_before: output::format_time(cert.not_before),
+                    not_after: output::format_time(cert.not_after),
+                    days_until_expiry: days,
+                    expiring: days < warn_days,
+                    expires_at: cert.not_after,
+                    subject: cert.subject,
+                    sans: cert.sans,
+                    issuer: cert.issuer,
+                    serial: cert.serial,
+                });
+            }
+        }
+    }
+    entries.sort_by_key(|e| e.expires_at);
+    entries
+}
+
+/// Render the entries as a table, highlighting the ones expiring soon
+fn table(entries: &[Entry], warn_days: i64) -> String {
+    let width = |f: fn(&Entry) -> String, header: &str| {
+        entries
+            .iter()
+            .map(|e| f(e).chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(header.len())
+    };
+    let secret = |e: &Entry| format!("{}/{}", e.namespace, e.secret);
+    let secret_width = width(secret, "SECRET");
+    let key_width = width(|e| e.key.clone(), "KEY");
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:10}  {:>5}  {:secret_width$}  {:key_width$}  SUBJECT",
+        "EXPIRES", "DAYS", "SECRET", "KEY"
+    );
+    for e in entries {
+        let days = format!("{:>5}", e.days_until_expiry);
+        let days = match e.days_until_expiry {
+            d if d < 0 => days.red().to_string(),
+            _ if e.expiring => days.yellow().to_string(),
+            _ => days,
+        };
+        let _ = writeln!(
+            out,
+            "{:10}  {}  {:secret_width$}  {:key_width$}  {}",
+            &e.not_after[..10.min(e.not_after.len())],
+            days,
+            secret(e),
+            e.key,
+            e.subject
+        );
+    }
+
+    let expired = entries.iter().filter(|e| e.days_until_expiry < 0).count();
+    let expiring = entries.iter().filter(|e| e.expiring).count() - expired;
+    let _ = writeln!(
+        out,
+        "\n{} certificates, {} expiring within {} days, {} expired",
+        entries.len(),
+        expiring,
+        warn_days,
+        expired
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{EC_CERT, ISSUED, RSA_CERT, RSA_KEY, secret};
+    use clap::Parser;
+
+    #[derive(Parser, Debug)]
+    struct Cli {
+        #[clap(flatten)]
+        args: CertsArgs,
+    }
+
+    fn secrets() -> Vec<Secret> {
+        vec![
+            secret(
+                "web-tls",
+                "kubernetes.io/tls",
+                &[
+                    ("tls.crt", RSA_CERT.as_bytes()),
+                    ("tls.key", RSA_KEY.as_bytes()),
+                ],
+            ),
+            secret(
+                "api",
+                "Opaque",
+                &[("ca.pem", EC_CERT.as_bytes()), ("token", b"abc123")],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let cli = Cli::try_parse_from(["certs", "-A", "--warn-days", "14", "-o", "json"]).unwrap();
+        assert!(cli.args.all_namespaces);
+        assert_eq!(cli.args.warn_days, 14);
+        assert_eq!(cli.args.output, CertsFormat::Json);
+
+        let cli = Cli::try_parse_from(["certs", "default"]).unwrap();
+        assert_eq!(cli.args.warn_days, 30);
+        assert!(Cli::try_parse_from(["certs"]).is_err());
+    }
+
+    #[test]
+    fn test_entries_sorted_by_expiry() {
+        let entries = entries(&secrets(), ISSUED, 30);
+        let found: Vec<(&str, &str, i64, bool)> = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.secret.as_str(),
+                    e.key.as_str(),
+                    e.days_until_expiry,
+                    e.expiring,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("api", "ca.pem", 30, false),
+                ("web-tls", "tls.crt", 365, false)
+            ]
+        );
+
+        let entries = super::entries(&secrets(), ISSUED + 10 * 86400, 30);
+        assert_eq!(entries[0].days_until_expiry, 20);
+        assert!(entries[0].expiring);
+        assert!(!entries[1].expiring);
+    }
+
+    #[test]
+    fn test_table() {
+        let out = table(&entries(&secrets(), ISSUED + 31 * 86400, 30), 30);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            "EXPIRES      DAYS  SECRET           KEY      SUBJECT"
+        );
+        assert!(lines[1].starts_with("2026-11-15"));
+        assert!(lines[1].contains("default/api"));
+        assert!(
+            lines[2]
+                .starts_with("2027-10-16    334  default/web-tls  tls.crt  CN=example.com, O=Acme")
+        );
+        assert_eq!(
+            lines[4],
+            "2 certificates, 0 expiring within 30 days, 1 expired"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json = serde_json::to_string(&entries(&secrets(), ISSUED, 30)).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json[1]["namespace"], "default");
+        assert_eq!(json[1]["secret"], "web-tls");
+        assert_eq!(json[1]["sans"][0], "DNS:example.com");
+        assert_eq!(json[1]["notAfter"], "2027-10-16T20:51:43Z");
+        assert_eq!(json[1]["daysUntilExpiry"], 365);
+        assert_eq!(json[1]["expiring"], false);
+        assert!(json[1].get("expiresAt").is_none());
+    }
+}
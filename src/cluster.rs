@@ -0,0 +1,169 @@
+//! Selecting which cluster to talk to from the kubeconfig
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Args;
+use kube::Client;
+use kube::config::{KubeConfigOptions, Kubeconfig};
+
+/// Options picking the kubeconfig, context and cluster to connect with
+#[derive(Args, Debug, Default, Clone)]
+pub struct ClusterArgs {
+    /// Kubeconfig context to use rather than the current context
+    #[clap(long, global = true)]
+    pub context: Option<String>,
+
+    /// Path to the kubeconfig file to use
+    #[clap(long, global = true, value_name = "PATH")]
+    kubeconfig: Option<PathBuf>,
+
+    /// Kubeconfig cluster to use rather than the one of the context
+    #[clap(long, global = true)]
+    cluster: Option<String>,
+}
+
+impl ClusterArgs {
+    /// The same options, but for a different context
+    pub fn with_context(&self, context: &str) -> Self {
+        ClusterArgs {
+            context: Some(context.to_string()),
+            ..self.clone()
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        self.context.is_none() && self.kubeconfig.is_none() && self.cluster.is_none()
+    }
+}
+
+/// A client along with the name of the context it was built from
+pub struct Connection {
+    pub client: Client,
+    pub context: String,
+}
+
+/// Connect to the cluster selected by the options
+///
+/// Without any options this behaves like `kubectl`, using the current context
+/// of `$KUBECONFIG` or `~/.kube/config` and falling back to the in-cluster
+/// service account.
+pub async fn connect(args: &ClusterArgs) -> anyhow::Result<Connection> {
+    if args.is_default() {
+        let config = kube::Config::infer().await?;
+        let context = context_name(args, Kubeconfig::read().ok().as_ref());
+        return Ok(Connection {
+            client: Client::try_from(config)?,
+            context,
+        });
+    }
+
+    let kubeconfig = match &args.kubeconfig {
+        Some(path) => Kubeconfig::read_from(path)
+            .with_context(|| format!("Unable to read kubeconfig '{}'", path.display()))?,
+        None => Kubeconfig::read().context("Unable to read kubeconfig")?,
+    };
+    let context = context_name(args, Some(&kubeconfig));
+    let options = KubeConfigOptions {
+        context: args.context.clone(),
+        cluster: args.cluster.clone(),
+        user: None,
+    };
+    let config = kube::Config::from_custom_kubeconfig(kubeconfig, &options)
+        .await
+        .with_context(|| format!("Unable to load kubeconfig context '{}'", context))?;
+    Ok(Connection {
+        client: Client::try_from(config)?,
+        context,
+    })
+}
+
+/// Name of the context that will be used, for showing to the user
+fn context_name(args: &ClusterArgs, kubeconfig: Option<&Kubeconfig>) -> String {
+    let name = args
+        .context
+        .clone()
+        .or_else(|| kubeconfig.and_then(|k| k.current_context.clone()))
+        .unwrap_or_else(|| "in-cluster".to_string());
+    match &args.cluster {
+        Some(cluster) => format!("{} (cluster {})", name, cluster),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kubeconfig() -> Kubeconfig {
+        Kubeconfig::from_yaml(
+            r#"
+apiVersion: v1
+kind: Config
+current-context: staging
+contexts:
+  - name: staging
+    context:
+      cluster: staging
+  - name: prod
+    context:
+      cluster: prod
+clusters:
+  - name: staging
+    cluster:
+      server: https://staging.example.com
+  - name: prod
+    cluster:
+      server: https://prod.example.com
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_context_name_defaults_to_current_context() {
+        assert_eq!(
+            context_name(&ClusterArgs::default(), Some(&kubeconfig())),
+            "staging"
+        );
+    }
+
+    #[test]
+    fn test_context_name_explicit_context() {
+        let args = ClusterArgs::default().with_context("prod");
+        assert_eq!(context_name(&args, Some(&kubeconfig())), "prod");
+    }
+
+    #[test]
+    fn test_context_name_with_cluster() {
+        let args = ClusterArgs {
+            cluster: Some("prod".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            context_name(&args, Some(&kubeconfig())),
+            "staging (cluster prod)"
+        );
+    }
+
+    #[test]
+    fn test_context_name_without_kubeconfig() {
+        assert_eq!(context_name(&ClusterArgs::default(), None), "in-cluster");
+    }
+
+    #[tokio::test]
+    async fn test_connect_unknown_context() {
+        let path =
+            std::env::temp_dir().join(format!("kube-secrets-test-{}.yaml", std::process::id()));
+        std::fs::write(&path, serde_yaml::to_string(&kubeconfig()).unwrap()).unwrap();
+        let args = ClusterArgs {
+            context: Some("qa".to_string()),
+            kubeconfig: Some(path.clone()),
+            cluster: None,
+        };
+        let result = connect(&args).await;
+        std::fs::remove_file(&path).unwrap();
+        let err = result.err().unwrap();
+        assert_eq!(err.to_string(), "Unable to load kubeconfig context 'qa'");
+    }
+}
@@ -0,0 +1,348 @@
+//! Comparing secrets between several sources, such as kube contexts
+//!
+//! Values are compared byte for byte. Unless values are asked for they're
+//! shown as a short SHA-256 fingerprint, which is enough to see which sources
+//! agree without putting the secret itself on screen.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::process::ExitCode;
+
+use anyhow::Context;
+use colorful::Colorful;
+use futures::future;
+use k8s_openapi::api::core::v1::Secret;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::filter::Filter;
+use crate::output::OutputFormat;
+use crate::{Config, cluster};
+
+/// The values of each secret from one source, keyed by the name used to pair
+/// secrets up between sources
+pub type Source = BTreeMap<String, BTreeMap<String, Vec<u8>>>;
+
+/// Build a source from listed secrets, pairing them by `namespace/name` or
+/// just by name when comparing different namespaces
+pub fn index(secrets: &[Secret], qualify_names: bool) -> Source {
+    secrets
+        .iter()
+        .map(|s| {
+            let name = s.metadata.name.clone().unwrap_or_default();
+            let id = match (&s.metadata.namespace, qualify_names) {
+                (Some(namespace), true) => format!("{}/{}", namespace, name),
+                _ => name,
+            };
+            let data = s
+                .data
+                .iter()
+                .flatten()
+                .map(|(key, value)| (key.clone(), value.0.clone()))
+                .collect();
+            (id, data)
+        })
+        .collect()
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Equal,
+    Different,
+    Missing,
+}
+
+/// One key of one secret, with its value in each source
+#[derive(Debug)]
+pub struct Row {
+    pub secret: String,
+    pub key: String,
+    pub values: Vec<Option<Vec<u8>>>,
+    pub status: Status,
+}
+
+/// Line up every key of every secret across the sources
+pub fn compare(sources: &[Source]) -> Vec<Row> {
+    let secrets: BTreeSet<&String> = sources.iter().flat_map(|source| source.keys()).collect();
+
+    let mut rows = Vec::new();
+    for secret in secrets {
+        let keys: BTreeSet<&String> = sources
+            .iter()
+            .filter_map(|source| source.get(secret))
+            .flat_map(|data| data.keys())
+            .collect();
+        for key in keys {
+            let values: Vec<Option<Vec<u8>>> = sources
+                .iter()
+                .map(|source| source.get(secret).and_then(|data| data.get(key)).cloned())
+                .collect();
+            let status = if values.iter().any(Option::is_none) {
+                Status::Missing
+            } else if values.windows(2).all(|pair| pair[0] == pair[1]) {
+                Status::Equal
+            } else {
+                Status::Different
+            };
+            rows.push(Row {
+                secret: secret.clone(),
+                key: key.clone(),
+                values,
+                status,
+            });
+        }
+    }
+    rows
+}
+
+/// Short SHA-256 fingerprint of a value
+pub fn fingerprint(value: &[u8]) -> String {
+    Sha256::digest(value)
+        .iter()
+        .take(4)
+        .fold(String::new(), |mut out, byte| {
+            let _ = write!(out, "{:02x}", byte);
+            out
+        })
+}
+
+/// How a value is shown, its fingerprint unless values were asked for
+pub fn display_value(value: &[u8], reveal: bool) -> String {
+    if reveal {
+        String::from_utf8_lossy(value).escape_debug().to_string()
+    } else {
+        fingerprint(value)
+    }
+}
+
+/// Fetch the selected secrets from each context and print how they compare
+///
+/// A namespace missing from one of the contexts is an error, rather than
+/// every key showing up as missing there.
+pub async fn run(config: &Config, filter: &Filter) -> anyhow::Result<ExitCode> {
+    if !matches!(config.output, OutputFormat::Text | OutputFormat::Json) {
+        anyhow::bail!("--contexts only supports text and JSON output");
+    }
+
+    let sources = future::try_join_all(config.contexts.iter().map(|context| async move {
+        let connection = cluster::connect(&config.cluster.with_context(context)).await?;
+        let client = connection.client;
+        let secrets = crate::select_secrets(config, filter, client.clone()).await?;
+        if secrets.is_empty() && !config.spans_namespaces() {
+            let namespace = config.namespace.as_deref().unwrap_or_default();
+            crate::ensure_namespace(client, namespace)
+                .await
+                .with_context(|| format!("Unable to compare context '{}'", context))?;
+        }
+        anyhow::Ok(index(&secrets, true))
+    }))
+    .await?;
+
+    let rows = compare(&sources);
+    if config.output == OutputFormat::Json {
+        println!("{}", to_json(&config.contexts, &rows, config.reveal)?);
+    } else {
+        print!("{}", matrix(&config.contexts, &rows, config.reveal));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Render the rows as a table per secret with a column for each source
+fn matrix(columns: &[String], rows: &[Row], reveal: bool) -> String {
+    let cell = |value: &Option<Vec<u8>>| match value {
+        Some(value) => display_value(value, reveal),
+        None => "-".to_string(),
+    };
+    let key_width = rows
+        .iter()
+        .map(|r| r.key.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max(3);
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            rows.iter()
+                .map(|r| cell(&r.values[i]).chars().count())
+                .max()
+                .unwrap_or_default()
+                .max(name.chars().count())
+        })
+        .collect();
+
+    let mut out = String::new();
+    let mut current = None;
+    for row in rows {
+        if current != Some(&row.secret) {
+            if current.is_some() {
+                out.push('\n');
+            }
+            current = Some(&row.secret);
+            let _ = writeln!(out, "{}:", row.secret.clone().light_blue());
+            let _ = write!(out, "  {:key_width$}", "KEY");
+            for (name, width) in columns.iter().zip(&widths) {
+                let _ = write!(out, "  {:width$}", name);
+            }
+            out.push('\n');
+        }
+
+        let _ = write!(out, "  {}", format!("{:key_width$}", row.key).light_green());
+        for (value, width) in row.values.iter().zip(&widths) {
+            let _ = write!(out, "  {:width$}", cell(value));
+        }
+        let status = match row.status {
+            Status::Equal => "equal".green().to_string(),
+            Status::Different => "different".red().to_string(),
+            Status::Missing => "missing".yellow().to_string(),
+        };
+        let _ = writeln!(out, "  {}", status);
+    }
+
+    let count = |status| rows.iter().filter(|r| r.status == status).count();
+    let _ = writeln!(
+        out,
+        "\n{} equal, {} different, {} missing",
+        count(Status::Equal),
+        count(Status::Different),
+        count(Status::Missing)
+    );
+    out
+}
+
+fn to_json(columns: &[String], rows: &[Row], reveal: bool) -> anyhow::Result<String> {
+    #[derive(Serialize)]
+    struct JsonRow<'a> {
+        secret: &'a str,
+        key: &'a str,
+        values: BTreeMap<&'a str, Option<String>>,
+        status: Status,
+    }
+
+    let rows: Vec<JsonRow> = rows
+        .iter()
+        .map(|row| JsonRow {
+            secret: &row.secret,
+            key: &row.key,
+            values: columns
+                .iter()
+                .zip(&row.values)
+                .map(|(name, value)| {
+                    (
+                        name.as_str(),
+                        value.as_deref().map(|v| display_value(v, reveal)),
+                    )
+                })
+                .collect(),
+            status: row.status,
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&rows)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::source;
+
+    fn statuses(rows: &[Row]) -> Vec<(&str, &str, Status)> {
+        rows.iter()
+            .map(|r| (r.secret.as_str(), r.key.as_str(), r.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_statuses() {
+        let staging = source(&[(
+            "default/db",
+            &[("user", "app"), ("password", "a"), ("host", "db")],
+        )]);
+        let prod = source(&[("default/db", &[("user", "app"), ("password", "b")])]);
+        let rows = compare(&[staging, prod]);
+        assert_eq!(
+            statuses(&rows),
+            [
+                ("default/db", "host", Status::Missing),
+                ("default/db", "password", Status::Different),
+                ("default/db", "user", Status::Equal),
+            ]
+        );
+        assert_eq!(rows[0].values, [Some(b"db".to_vec()), None]);
+    }
+
+    #[test]
+    fn test_compare_secret_missing_from_a_source() {
+        let a = source(&[("default/api", &[("token", "x")])]);
+        let b = source(&[]);
+        let c = source(&[("default/api", &[("token", "x")])]);
+        let rows = compare(&[a, b, c]);
+        assert_eq!(statuses(&rows), [("default/api", "token", Status::Missing)]);
+        assert_eq!(rows[0].values[1], None);
+    }
+
+    #[test]
+    fn test_compare_three_way_difference() {
+        let a = source(&[("s", &[("k", "1")])]);
+        let b = source(&[("s", &[("k", "1")])]);
+        let c = source(&[("s", &[("k", "2")])]);
+        assert_eq!(
+            statuses(&compare(&[a, b, c])),
+            [("s", "k", Status::Different)]
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        // sha256("hunter2") = f52fbd32b2b3b86ff88ef6c490628285f482af15ddcb29541f94bcf526a3f6c7
+        assert_eq!(fingerprint(b"hunter2"), "f52fbd32");
+        assert_eq!(display_value(b"hunter2", true), "hunter2");
+        assert_eq!(display_value(b"a\nb", true), "a\\nb");
+    }
+
+    #[test]
+    fn test_matrix_masks_values() {
+        let a = source(&[("default/db", &[("password", "hunter2")])]);
+        let b = source(&[("default/db", &[("password", "hunter3")])]);
+        let out = matrix(
+            &["staging".to_string(), "prod".to_string()],
+            &compare(&[a, b]),
+            false,
+        );
+        assert!(!out.contains("hunter2"));
+        assert!(out.contains("f52fbd32"));
+        assert!(out.contains("staging"));
+        assert!(out.contains("0 equal, 1 different, 0 missing"));
+    }
+
+    #[test]
+    fn test_json_rows() {
+        let a = source(&[("default/db", &[("password", "hunter2")])]);
+        let b = source(&[]);
+        let json = to_json(
+            &["staging".to_string(), "prod".to_string()],
+            &compare(&[a, b]),
+            false,
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json[0]["secret"], "default/db");
+        assert_eq!(json[0]["values"]["staging"], "f52fbd32");
+        assert!(json[0]["values"]["prod"].is_null());
+        assert_eq!(json[0]["status"], "missing");
+    }
+
+    #[test]
+    fn test_index_qualifies_names() {
+        let s = Secret {
+            metadata: k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta {
+                name: Some("db".to_string()),
+                namespace: Some("app-prod".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(index(std::slice::from_ref(&s), true).contains_key("app-prod/db"));
+        assert!(index(&[s], false).contains_key("db"));
+    }
+}
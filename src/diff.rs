@@ -0,0 +1,303 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use colorful::Colorful;
+use k8s_openapi::api::core::v1::Secret;
+use kube::{Api, Client};
+
+use crate::Config;
+use crate::compare::{self, Source};
+use crate::filter::{self, Filter};
+use crate::manifests;
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Namespace to compare from, e.g. staging. With `--file` the namespace
+    /// to compare the manifests with, overriding their own namespace
+    #[clap(required_unless_present = "files")]
+    left: Option<String>,
+
+    /// Namespace to compare to, e.g. production
+    #[clap(required_unless_present = "files", conflicts_with = "files")]
+    right: Option<String>,
+
+    /// Only compare secrets whose name contains this
+    query: Option<String>,
+
+    /// Compare local Secret manifests with the live secrets. May be repeated
+    /// and may be a directory
+    #[clap(short = 'f', long = "file", value_name = "PATH")]
+    files: Vec<PathBuf>,
+
+    /// Compare secrets of every type
+    #[clap(short = 'a', long)]
+    show_all: bool,
+}
+
+/// A difference between two versions of a secret's key
+#[derive(Debug, PartialEq, Eq)]
+pub enum KeyDiff {
+    Added(String, Vec<u8>),
+    Removed(String, Vec<u8>),
+    Changed(String, Vec<u8>, Vec<u8>),
+}
+
+/// A difference between the secrets of two sources, paired by name
+#[derive(Debug, PartialEq, Eq)]
+pub enum SecretDiff {
+    Added(String),
+    Removed(String),
+    Changed(String, Vec<KeyDiff>),
+}
+
+/// Find the secrets and keys that were added, removed or changed going from
+/// `left` to `right`
+pub fn diff(left: &Source, right: &Source) -> Vec<SecretDiff> {
+    let mut names: Vec<&String> = left.keys().chain(right.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut diffs = Vec::new();
+    for name in names {
+        let (old, new) = match (left.get(name), right.get(name)) {
+            (Some(old), Some(new)) => (old, new),
+            (Some(_), None) => {
+                diffs.push(SecretDiff::Removed(name.clone()));
+                continue;
+            }
+            (None, _) => {
+                diffs.push(SecretDiff::Added(name.clone()));
+                continue;
+            }
+        };
+
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        let changes: Vec<KeyDiff> = keys
+            .into_iter()
+            .filter_map(|key| match (old.get(key), new.get(key)) {
+                (Some(a), Some(b)) if a == b => None,
+                (Some(a), Some(b)) => Some(KeyDiff::Changed(key.clone(), a.clone(), b.clone())),
+                (Some(a), None) => Some(KeyDiff::Removed(key.clone(), a.clone())),
+                (None, Some(b)) => Some(KeyDiff::Added(key.clone(), b.clone())),
+                (None, None) => None,
+            })
+            .collect();
+        if !changes.is_empty() {
+            diffs.push(SecretDiff::Changed(name.clone(), changes));
+        }
+    }
+    diffs
+}
+
+/// Compare the secrets of two namespaces, or local manifests with the live
+/// secrets, exiting with 1 when they differ. Errors exit with 2 from `main`.
+pub async fn run(client: Client, config: &Config, args: &DiffArgs) -> anyhow::Result<ExitCode> {
+    if !args.files.is_empty() {
+        return run_files(client, config, args).await;
+    }
+
+    let left = args.left.as_deref().unwrap_or_default();
+    let right = args.right.as_deref().unwrap_or_default();
+    let filter = Filter::new(&Config {
+        query: args.query.clone(),
+        show_all: args.show_all,
+        ..Default::default()
+    })?;
+
+    // A misspelt namespace would otherwise show every secret as removed
+    futures::try_join!(
+        crate::ensure_namespace(client.clone(), left),
+        crate::ensure_namespace(client.clone(), right)
+    )?;
+    let (old, new) = futures::try_join!(
+        fetch(client.clone(), &filter, left),
+        fetch(client, &filter, right)
+    )?;
+    report(
+        &diff(&old, &new),
+        config,
+        &format!("'{}' and '{}'", left, right),
+    )
+}
+
+/// Compare the secrets in local manifests with their live versions, as
+/// `kubectl apply` would see them. Secrets only in the cluster are ignored.
+async fn run_files(client: Client, config: &Config, args: &DiffArgs) -> anyhow::Result<ExitCode> {
+    let mut local = manifests::load(&args.files)?;
+    if let Some(namespace) = &args.left {
+        crate::ensure_namespace(client.clone(), namespace).await?;
+    }
+    for s in local.iter_mut() {
+        if let Some(namespace) = &args.left {
+            s.metadata.namespace = Some(namespace.clone());
+        } else if s.metadata.namespace.is_none() {
+            s.metadata.namespace = Some(client.default_namespace().to_string());
+        }
+    }
+
+    let live = futures::future::try_join_all(local.iter().map(|s| {
+        let namespace = s.metadata.namespace.as_deref().unwrap_or_default();
+        let api: Api<Secret> = Api::namespaced(client.clone(), namespace);
+        async move {
+            api.get_opt(s.metadata.name.as_deref().unwrap_or_default())
+                .await
+        }
+    }))
+    .await?;
+    let live: Vec<Secret> = live.into_iter().flatten().collect();
+
+    let diffs = diff(&compare::index(&live, true), &compare::index(&local, true));
+    report(&diffs, config, "the manifests and the cluster")
+}
+
+fn report(diffs: &[SecretDiff], config: &Config, compared: &str) -> anyhow::Result<ExitCode> {
+    print!("{}", render(diffs, config.reveal));
+    if diffs.is_empty() {
+        println!("No differences between {}", compared);
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+async fn fetch(client: Client, filter: &Filter, namespace: &str) -> anyhow::Result<Source> {
+    let api: Api<Secret> = Api::namespaced(client, namespace);
+    let secrets: Vec<Secret> = api
+        .list(&filter.list_params())
+        .await?
+        .into_iter()
+        .filter(|s| filter::display_secret(filter, s))
+        .collect();
+    Ok(compare::index(&secrets, false))
+}
+
+/// Render the differences in a diff like format, one line per change
+pub fn render(diffs: &[SecretDiff], reveal: bool) -> String {
+    let value = |v: &[u8]| compare::display_value(v, reveal);
+
+    let mut out = String::new();
+    for d in diffs {
+        let line = match d {
+            SecretDiff::Added(name) => format!("+ {}", name).green(),
+            SecretDiff::Removed(name) => format!("- {}", name).red(),
+            SecretDiff::Changed(name, _) => format!("~ {}", name).yellow(),
+        };
+        let _ = writeln!(out, "{}", line);
+
+        if let SecretDiff::Changed(_, keys) = d {
+            for k in keys {
+                let line = match k {
+                    KeyDiff::Added(key, new) => format!("+ {}: {}", key, value(new)).green(),
+                    KeyDiff::Removed(key, old) => format!("- {}: {}", key, value(old)).red(),
+                    KeyDiff::Changed(key, old, new) => {
+                        format!("~ {}: {} -> {}", key, value(old), value(new)).yellow()
+                    }
+                };
+                let _ = writeln!(out, "    {}", line);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::source;
+    use clap::Parser;
+
+    #[derive(Parser, Debug)]
+    struct Cli {
+        #[clap(flatten)]
+        args: DiffArgs,
+    }
+
+    #[test]
+    fn test_parse_namespaces_and_query() {
+        let cli = Cli::try_parse_from(["diff", "app-stage", "app-prod", "db"]).unwrap();
+        assert_eq!(cli.args.left.as_deref(), Some("app-stage"));
+        assert_eq!(cli.args.right.as_deref(), Some("app-prod"));
+        assert_eq!(cli.args.query.as_deref(), Some("db"));
+        assert!(cli.args.files.is_empty());
+    }
+
+    #[test]
+    fn test_parse_requires_two_namespaces() {
+        assert!(Cli::try_parse_from(["diff", "app-stage"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_files() {
+        let cli = Cli::try_parse_from(["diff", "-f", "secrets/", "--file", "db.yaml"]).unwrap();
+        assert_eq!(
+            cli.args.files,
+            [PathBuf::from("secrets/"), PathBuf::from("db.yaml")]
+        );
+        assert!(cli.args.left.is_none());
+
+        let cli = Cli::try_parse_from(["diff", "--file", "db.yaml", "app-prod"]).unwrap();
+        assert_eq!(cli.args.left.as_deref(), Some("app-prod"));
+    }
+
+    #[test]
+    fn test_parse_files_with_two_namespaces_fails() {
+        assert!(
+            Cli::try_parse_from(["diff", "--file", "db.yaml", "app-stage", "app-prod"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let a = source(&[("db", &[("user", "app")])]);
+        assert!(diff(&a, &a.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_added_and_removed_secrets() {
+        let stage = source(&[("old", &[]), ("shared", &[("k", "v")])]);
+        let prod = source(&[("new", &[]), ("shared", &[("k", "v")])]);
+        assert_eq!(
+            diff(&stage, &prod),
+            [
+                SecretDiff::Added("new".to_string()),
+                SecretDiff::Removed("old".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_keys() {
+        let stage = source(&[("db", &[("user", "app"), ("password", "a"), ("host", "db")])]);
+        let prod = source(&[(
+            "db",
+            &[("user", "app"), ("password", "b"), ("port", "5432")],
+        )]);
+        assert_eq!(
+            diff(&stage, &prod),
+            [SecretDiff::Changed(
+                "db".to_string(),
+                vec![
+                    KeyDiff::Removed("host".to_string(), b"db".to_vec()),
+                    KeyDiff::Changed("password".to_string(), b"a".to_vec(), b"b".to_vec()),
+                    KeyDiff::Added("port".to_string(), b"5432".to_vec()),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_render_masks_values() {
+        let stage = source(&[("db", &[("password", "hunter2")])]);
+        let prod = source(&[("db", &[("password", "hunter3")])]);
+        let out = render(&diff(&stage, &prod), false);
+        assert!(out.contains("password: f52fbd32 -> "));
+        assert!(!out.contains("hunter2"));
+
+        let out = render(&diff(&stage, &prod), true);
+        assert!(out.contains("password: hunter2 -> hunter3"));
+    }
+}
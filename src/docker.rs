@@ -0,0 +1,120 @@
+//! Decoding the registry credentials of image pull secrets
+//!
+//! `kubernetes.io/dockerconfigjson` secrets hold a `~/.docker/config.json`
+//! under `.dockerconfigjson`, the older `kubernetes.io/dockercfg` ones hold
+//! just its `auths` object under `.dockercfg`.
+
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+
+/// The credentials for one registry
+#[derive(Debug, PartialEq, Eq)]
+pub struct Credential {
+    pub registry: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default)]
+    auths: BTreeMap<String, Auth>,
+}
+
+#[derive(Deserialize)]
+struct Auth {
+    username: Option<String>,
+    password: Option<String>,
+    /// Base64 of `username:password`
+    auth: Option<String>,
+    email: Option<String>,
+}
+
+/// Parse the credentials out of a docker config key, `None` for other keys or
+/// when the value isn't a valid docker config
+pub fn parse(key: &str, value: &[u8]) -> Option<Vec<Credential>> {
+    let auths = match key {
+        ".dockerconfigjson" => serde_json::from_slice::<Config>(value).ok()?.auths,
+        ".dockercfg" => serde_json::from_slice(value).ok()?,
+        _ => return None,
+    };
+    Some(auths.into_iter().map(credential).collect())
+}
+
+/// Fill in the username and password from `auth` when they aren't given
+/// separately, which is how `kubectl create secret docker-registry` and most
+/// tools write them
+fn credential((registry, auth): (String, Auth)) -> Credential {
+    let decoded = auth
+        .auth
+        .and_then(|a| STANDARD.decode(a).ok())
+        .and_then(|a| String::from_utf8(a).ok());
+    let (username, password) = match decoded.as_deref().and_then(|d| d.split_once(':')) {
+        Some((username, password)) => (Some(username.to_string()), Some(password.to_string())),
+        None => (None, None),
+    };
+
+    Credential {
+        registry,
+        username: auth.username.or(username),
+        password: auth.password.or(password),
+        email: auth.email,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dockerconfigjson() {
+        // auth is base64 of "bob:hunter2"
+        let value = br#"{"auths":{"ghcr.io":{"auth":"Ym9iOmh1bnRlcjI="},"registry.example.com":{"username":"ci","password":"s3cret","email":"ci@example.com"}}}"#;
+        assert_eq!(
+            parse(".dockerconfigjson", value).unwrap(),
+            [
+                Credential {
+                    registry: "ghcr.io".to_string(),
+                    username: Some("bob".to_string()),
+                    password: Some("hunter2".to_string()),
+                    email: None,
+                },
+                Credential {
+                    registry: "registry.example.com".to_string(),
+                    username: Some("ci".to_string()),
+                    password: Some("s3cret".to_string()),
+                    email: Some("ci@example.com".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_dockercfg() {
+        let value = br#"{"https://index.docker.io/v1/":{"auth":"Ym9iOmh1bnRlcjI=","email":"bob@example.com"}}"#;
+        let credentials = parse(".dockercfg", value).unwrap();
+        assert_eq!(credentials[0].registry, "https://index.docker.io/v1/");
+        assert_eq!(credentials[0].username.as_deref(), Some("bob"));
+        assert_eq!(credentials[0].email.as_deref(), Some("bob@example.com"));
+    }
+
+    #[test]
+    fn test_parse_password_with_colon() {
+        // auth is base64 of "bob:a:b"
+        let value = br#"{"auths":{"ghcr.io":{"auth":"Ym9iOmE6Yg=="}}}"#;
+        let credentials = parse(".dockerconfigjson", value).unwrap();
+        assert_eq!(credentials[0].password.as_deref(), Some("a:b"));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse(".dockerconfigjson", b"not json").is_none());
+        assert!(parse("config.json", br#"{"auths":{}}"#).is_none());
+        let value = br#"{"auths":{"ghcr.io":{"auth":"!!"}}}"#;
+        assert_eq!(parse(".dockerconfigjson", value).unwrap()[0].username, None);
+    }
+}
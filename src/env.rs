@@ -0,0 +1,177 @@
+//! Helpers for turning secret keys and values into environment variables
+//!
+//! Secret keys may contain `.` and `-` which aren't valid in environment
+//! variable names. Keys are converted by replacing every character other than
+//! ASCII letters, digits and `_` with `_`, and prefixing a `_` when the result
+//! would start with a digit. So `tls.crt` becomes `tls_crt` and `1-key`
+//! becomes `_1_key`. Two keys of a secret converting to the same name, like
+//! `api-key` and `api_key`, is an error.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Convert a secret key into a valid environment variable name
+pub fn var_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Convert every key of a secret with [`var_name`], failing when two keys
+/// would end up as the same variable rather than one silently replacing the
+/// other
+pub fn var_names<'a>(
+    secret_name: &str,
+    keys: impl IntoIterator<Item = &'a str>,
+) -> anyhow::Result<BTreeMap<&'a str, String>> {
+    let mut names = BTreeMap::new();
+    let mut taken: HashMap<String, &str> = HashMap::new();
+    for key in keys {
+        let name = var_name(key);
+        if let Some(other) = taken.insert(name.clone(), key) {
+            anyhow::bail!(
+                "Keys '{}' and '{}' of secret '{}' would both become the variable '{}'",
+                other,
+                key,
+                secret_name,
+                name
+            );
+        }
+        names.insert(key, name);
+    }
+    Ok(names)
+}
+
+/// Quote a value for use in a dotenv file
+///
+/// Plain values are left bare, anything else is single quoted so it's taken
+/// literally. Values containing single quotes or line breaks fall back to
+/// double quotes with backslash escapes, which every common dotenv parser
+/// understands. `$` is escaped there too, as loaders like docker compose
+/// expand variables in double quoted values.
+pub fn dotenv_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(is_plain) {
+        return value.to_string();
+    }
+    if !value.contains(['\'', '\n', '\r']) {
+        return format!("'{}'", value);
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quote a value so a POSIX shell reads it back verbatim
+///
+/// Single quotes are used unless the value contains control characters such
+/// as newlines, in which case ANSI-C `$'...'` quoting keeps it on one line.
+pub fn shell_quote(value: &str) -> String {
+    if !value.chars().any(|c| c.is_control()) {
+        return format!("'{}'", value.replace('\'', "'\\''"));
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 3);
+    quoted.push_str("$'");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\'' => quoted.push_str("\\'"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+fn is_plain(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_-./:@+,".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_var_name_keeps_valid_names() {
+        assert_eq!(var_name("DATABASE_URL"), "DATABASE_URL");
+        assert_eq!(var_name("password"), "password");
+    }
+
+    #[test]
+    fn test_var_name_replaces_invalid_characters() {
+        assert_eq!(var_name("tls.crt"), "tls_crt");
+        assert_eq!(var_name("api-key"), "api_key");
+        assert_eq!(var_name("a b"), "a_b");
+    }
+
+    #[test]
+    fn test_var_name_leading_digit() {
+        assert_eq!(var_name("1-key"), "_1_key");
+        assert_eq!(var_name(""), "_");
+    }
+
+    #[test]
+    fn test_var_names() {
+        let names = var_names("app", ["tls.crt", "password"]).unwrap();
+        assert_eq!(names["tls.crt"], "tls_crt");
+        assert_eq!(names["password"], "password");
+    }
+
+    #[test]
+    fn test_var_names_rejects_collisions() {
+        let err = var_names("app", ["api-key", "api_key"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Keys 'api-key' and 'api_key' of secret 'app' would both become the variable 'api_key'"
+        );
+    }
+
+    #[test]
+    fn test_dotenv_quote() {
+        assert_eq!(dotenv_quote("admin"), "admin");
+        assert_eq!(
+            dotenv_quote("postgres://u@db:5432/app"),
+            "postgres://u@db:5432/app"
+        );
+        assert_eq!(dotenv_quote(""), "''");
+        assert_eq!(dotenv_quote("p@ss word$"), "'p@ss word$'");
+        assert_eq!(dotenv_quote("it's"), "\"it's\"");
+        assert_eq!(dotenv_quote("a\nb \"c\" \\"), "\"a\\nb \\\"c\\\" \\\\\"");
+        assert_eq!(dotenv_quote("it's $HOME"), "\"it's \\$HOME\"");
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("admin"), "'admin'");
+        assert_eq!(shell_quote("$HOME `id`"), "'$HOME `id`'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote("a\nb's\\"), "$'a\\nb\\'s\\\\'");
+        assert_eq!(shell_quote("\x1b"), "$'\\x1b'");
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use anyhow::Context;
+use clap::Args;
+use k8s_openapi::api::core::v1::Secret;
+use kube::{Api, Client};
+
+use crate::env;
+
+#[derive(Args, Debug)]
+pub struct ExecArgs {
+    namespace: String,
+
+    /// Secrets to load, later secrets override keys of earlier ones
+    #[clap(required = true)]
+    secrets: Vec<String>,
+
+    /// Prefix added to every environment variable name
+    #[clap(long)]
+    prefix: Option<String>,
+
+    /// Command to run, given after `--`
+    #[clap(last = true, required = true)]
+    command: Vec<String>,
+}
+
+/// Fetch the secrets and spawn the command, returning its exit code
+///
+/// Values are only ever handed to the child process, they aren't printed or
+/// written anywhere.
+pub async fn run(client: Client, args: &ExecArgs) -> anyhow::Result<ExitCode> {
+    let api: Api<Secret> = Api::namespaced(client, &args.namespace);
+    let mut vars = BTreeMap::new();
+    for name in &args.secrets {
+        let s = crate::fetch_secret(&api, &args.namespace, name).await?;
+        vars.extend(env_vars(&s, args.prefix.as_deref().unwrap_or_default())?);
+    }
+
+    let mut child = tokio::process::Command::new(&args.command[0])
+        .args(&args.command[1..])
+        .envs(&vars)
+        .spawn()
+        .with_context(|| format!("Unable to run '{}'", args.command[0]))?;
+
+    // The child shares our terminal and receives interrupts itself, so keep
+    // waiting for it rather than exiting out from under it
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status?,
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    };
+
+    Ok(ExitCode::from(exit_code(status)))
+}
+
+/// Build the environment variables for every key of a secret, keys that
+/// would become the same variable are an error
+fn env_vars(s: &Secret, prefix: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let secret_name = s.metadata.name.as_deref().unwrap_or_default();
+    let data = s.data.clone().unwrap_or_default();
+    let names = env::var_names(secret_name, data.keys().map(String::as_str))?;
+    let mut vars = BTreeMap::new();
+    for (key, value) in data.iter() {
+        let value = String::from_utf8(value.0.clone()).map_err(|_| {
+            anyhow::anyhow!(
+                "Key '{}' of secret '{}' is not valid UTF-8",
+                key,
+                secret_name
+            )
+        })?;
+        vars.insert(format!("{}{}", prefix, names[key.as_str()]), value);
+    }
+    Ok(vars)
+}
+
+#[cfg(unix)]
+fn exit_code(status: std::process::ExitStatus) -> u8 {
+    use std::os::unix::process::ExitStatusExt;
+
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code as u8,
+        // Mirror the shell convention for processes killed by a signal
+        (None, Some(signal)) => 128 + signal as u8,
+        (None, None) => 1,
+    }
+}
+
+#[cfg(not(unix))]
+fn exit_code(status: std::process::ExitStatus) -> u8 {
+    status.code().map(|code| code as u8).unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::secret;
+    use clap::Parser;
+
+    #[derive(Parser, Debug)]
+    struct Cli {
+        #[clap(flatten)]
+        args: ExecArgs,
+    }
+
+    #[test]
+    fn test_parse_multiple_secrets_and_command() {
+        let cli = Cli::try_parse_from([
+            "exec", "default", "app", "db", "--prefix", "APP_", "--", "env", "-0",
+        ])
+        .unwrap();
+        assert_eq!(cli.args.namespace, "default");
+        assert_eq!(cli.args.secrets, ["app", "db"]);
+        assert_eq!(cli.args.prefix.as_deref(), Some("APP_"));
+        assert_eq!(cli.args.command, ["env", "-0"]);
+    }
+
+    #[test]
+    fn test_env_vars_converts_names() {
+        let vars = env_vars(
+            &secret(
+                "app",
+                "Opaque",
+                &[("DB_URL", b"postgres://db"), ("api-key", b"abc")],
+            ),
+            "",
+        )
+        .unwrap();
+        assert_eq!(vars["DB_URL"], "postgres://db");
+        assert_eq!(vars["api_key"], "abc");
+    }
+
+    #[test]
+    fn test_env_vars_prefix() {
+        let vars = env_vars(
+            &secret("app", "Opaque", &[("password", b"hunter2")]),
+            "APP_",
+        )
+        .unwrap();
+        assert_eq!(vars["APP_password"], "hunter2");
+        assert_eq!(vars.len(), 1);
+    }
+
+    #[test]
+    fn test_env_vars_rejects_colliding_names() {
+        let err = env_vars(
+            &secret("app", "Opaque", &[("api-key", b"a"), ("api_key", b"b")]),
+            "",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("'api-key' and 'api_key'"));
+    }
+
+    #[test]
+    fn test_env_vars_rejects_binary_values() {
+        let err =
+            env_vars(&secret("app", "Opaque", &[("keystore", &[0xfe, 0xed])]), "").unwrap_err();
+        assert!(err.to_string().contains("keystore"));
+    }
+}
@@ -0,0 +1,763 @@
+use anyhow::Context;
+use globset::{GlobBuilder, GlobMatcher};
+use k8s_openapi::api::core::v1::Secret;
+use kube::api::ListParams;
+use kube_runtime::watcher;
+use regex::{Regex, RegexBuilder};
+
+use crate::Config;
+
+/// Secret types shown when no `--type` is given, the ones typically read by
+/// humans. TLS certificates, Docker credentials, service account tokens and
+/// Helm releases are left out.
+pub const DEFAULT_TYPES: &[&str] = &[
+    "Opaque",
+    "kubernetes.io/basic-auth",
+    "kubernetes.io/ssh-auth",
+];
+
+/// The type of a secret, which the API server treats as `Opaque` when unset
+pub fn secret_type(s: &Secret) -> &str {
+    match s.type_.as_deref() {
+        None | Some("") => "Opaque",
+        Some(t) => t,
+    }
+}
+
+/// How the query is compared against secret names
+#[derive(Debug)]
+enum NameMatcher {
+    /// Plain substring match, the default
+    Contains(String),
+    /// Substring match against the lowercased name, holds the lowercased query
+    ContainsIgnoreCase(String),
+    /// Regular expression which may match anywhere in the name
+    Regex(Regex),
+    /// Glob pattern which has to match the whole name
+    Glob(GlobMatcher),
+}
+
+impl NameMatcher {
+    fn new(c: &Config, query: &str) -> anyhow::Result<Self> {
+        if c.regex {
+            let re = RegexBuilder::new(query)
+                .case_insensitive(c.ignore_case)
+                .build()
+                .with_context(|| format!("Invalid regular expression '{}'", query))?;
+            Ok(NameMatcher::Regex(re))
+        } else if c.glob {
+            let glob = GlobBuilder::new(query)
+                .case_insensitive(c.ignore_case)
+                .build()
+                .with_context(|| format!("Invalid glob pattern '{}'", query))?;
+            Ok(NameMatcher::Glob(glob.compile_matcher()))
+        } else if c.ignore_case {
+            Ok(NameMatcher::ContainsIgnoreCase(query.to_lowercase()))
+        } else {
+            Ok(NameMatcher::Contains(query.to_string()))
+        }
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        match self {
+            NameMatcher::Contains(query) => name.contains(query.as_str()),
+            NameMatcher::ContainsIgnoreCase(query) => name.to_lowercase().contains(query.as_str()),
+            NameMatcher::Regex(re) => re.is_match(name),
+            NameMatcher::Glob(glob) => glob.is_match(name),
+        }
+    }
+}
+
+/// The filters given on the command line, compiled up front so invalid
+/// patterns are reported before we talk to the cluster
+#[derive(Debug)]
+pub struct Filter {
+    /// Types to show, `None` meaning every type
+    include_types: Option<Vec<String>>,
+    exclude_types: Vec<String>,
+    name: Option<NameMatcher>,
+    label_selector: Option<String>,
+    field_selector: Option<String>,
+    grep: Option<Regex>,
+    matching_keys: bool,
+}
+
+impl Filter {
+    pub fn new(c: &Config) -> anyhow::Result<Self> {
+        let name = match &c.query {
+            Some(q) => Some(NameMatcher::new(c, q)?),
+            None => None,
+        };
+        let grep = match &c.grep {
+            Some(pattern) => Some(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(c.ignore_case)
+                    .build()
+                    .with_context(|| format!("Invalid --grep pattern '{}'", pattern))?,
+            ),
+            None => None,
+        };
+
+        // Only giving exclusions means "everything but these"
+        let include_types = if c.show_all || (c.types.is_empty() && !c.exclude_types.is_empty()) {
+            None
+        } else if c.types.is_empty() {
+            Some(DEFAULT_TYPES.iter().map(|t| t.to_string()).collect())
+        } else {
+            Some(c.types.clone())
+        };
+
+        Ok(Filter {
+            include_types,
+            exclude_types: c.exclude_types.clone(),
+            name,
+            label_selector: c.selector.clone(),
+            field_selector: c.field_selector.clone(),
+            grep,
+            matching_keys: c.matching_keys,
+        })
+    }
+
+    /// Parameters for listing secrets, so selectors are applied server side
+    pub fn list_params(&self) -> ListParams {
+        let mut params = ListParams::default();
+        if let Some(labels) = &self.label_selector {
+            params = params.labels(labels);
+        }
+        if let Some(fields) = &self.field_selector {
+            params = params.fields(fields);
+        }
+        params
+    }
+
+    /// The same selectors as [`Filter::list_params`], for watching secrets
+    pub fn watcher_config(&self) -> watcher::Config {
+        let mut config = watcher::Config::default();
+        if let Some(labels) = &self.label_selector {
+            config = config.labels(labels);
+        }
+        if let Some(fields) = &self.field_selector {
+            config = config.fields(fields);
+        }
+        config
+    }
+
+    /// The `--grep` pattern, used to highlight matches
+    pub fn grep(&self) -> Option<&Regex> {
+        self.grep.as_ref()
+    }
+
+    /// Whether a key or its decoded value matches the `--grep` pattern
+    fn grep_matches(&self, key: &str, value: &[u8]) -> bool {
+        match &self.grep {
+            Some(re) => {
+                re.is_match(key) || std::str::from_utf8(value).is_ok_and(|v| re.is_match(v))
+            }
+            None => true,
+        }
+    }
+
+    /// Drop the keys that didn't match `--grep` when only matching keys
+    /// should be shown
+    pub fn retain_matching_keys(&self, s: &mut Secret) {
+        if self.matching_keys
+            && let Some(data) = &mut s.data
+        {
+            data.retain(|key, value| self.grep_matches(key, &value.0));
+        }
+    }
+
+    fn shows_type(&self, type_: &str) -> bool {
+        let included = match &self.include_types {
+            Some(types) => types.iter().any(|t| t == type_),
+            None => true,
+        };
+        included && !self.exclude_types.iter().any(|t| t == type_)
+    }
+}
+
+pub fn display_secret(f: &Filter, s: &Secret) -> bool {
+    if !f.shows_type(secret_type(s)) {
+        return false;
+    }
+
+    if f.grep.is_some()
+        && !s
+            .data
+            .iter()
+            .flatten()
+            .any(|(k, v)| f.grep_matches(k, &v.0))
+    {
+        return false;
+    }
+
+    let matcher = match &f.name {
+        Some(m) => m,
+        None => return true,
+    };
+
+    // Filter the name against our query
+    let secret_name = s.metadata.name.as_deref().unwrap_or_default();
+    matcher.is_match(secret_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::secret;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+    /// Helper to compile the filter for a test Config
+    fn filter(c: &Config) -> Filter {
+        Filter::new(c).unwrap()
+    }
+
+    // ============================================================================
+    // display_secret Logic Tests
+    // ============================================================================
+
+    #[test]
+    fn test_display_secret_opaque_no_query() {
+        let config = Config {
+            show_all: false,
+            namespace: Some("default".to_string()),
+            query: None,
+            ..Default::default()
+        };
+        let secret = secret("my-secret", "Opaque", &[]);
+        assert!(display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_opaque_with_matching_query() {
+        let config = Config {
+            show_all: false,
+            namespace: Some("default".to_string()),
+            query: Some("token".to_string()),
+            ..Default::default()
+        };
+        let secret = secret("api-token", "Opaque", &[]);
+        assert!(display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_opaque_with_non_matching_query() {
+        let config = Config {
+            show_all: false,
+            namespace: Some("default".to_string()),
+            query: Some("cert".to_string()),
+            ..Default::default()
+        };
+        let secret = secret("api-token", "Opaque", &[]);
+        assert!(!display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_non_opaque_filtered_by_default() {
+        let config = Config {
+            show_all: false,
+            namespace: Some("default".to_string()),
+            query: None,
+            ..Default::default()
+        };
+        let secret = secret("tls-cert", "kubernetes.io/tls", &[]);
+        assert!(!display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_non_opaque_shown_with_show_all() {
+        let config = Config {
+            show_all: true,
+            namespace: Some("default".to_string()),
+            query: None,
+            ..Default::default()
+        };
+        let secret = secret("tls-cert", "kubernetes.io/tls", &[]);
+        assert!(display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_show_all_filters_on_query() {
+        let config = Config {
+            show_all: true,
+            namespace: Some("default".to_string()),
+            query: Some("tls".to_string()),
+            ..Default::default()
+        };
+        let secret = secret("my-tls-cert", "kubernetes.io/tls", &[]);
+        assert!(display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_show_all_filters_out_non_matching() {
+        let config = Config {
+            show_all: true,
+            namespace: Some("default".to_string()),
+            query: Some("db".to_string()),
+            ..Default::default()
+        };
+        let secret = secret("tls-cert", "kubernetes.io/tls", &[]);
+        assert!(!display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_case_sensitive_query() {
+        let config = Config {
+            show_all: true,
+            namespace: Some("default".to_string()),
+            query: Some("TOKEN".to_string()),
+            ..Default::default()
+        };
+        let secret = secret("api-token", "Opaque", &[]);
+        assert!(!display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_query_matches_substring() {
+        let config = Config {
+            show_all: false,
+            namespace: Some("default".to_string()),
+            query: Some("api".to_string()),
+            ..Default::default()
+        };
+        let secret = secret("my-api-credentials", "Opaque", &[]);
+        assert!(display_secret(&filter(&config), &secret));
+    }
+
+    // ============================================================================
+    // Common Kubernetes Secret Types
+    // ============================================================================
+
+    #[test]
+    fn test_display_secret_docker_config_filtered() {
+        let config = Config {
+            show_all: false,
+            namespace: Some("default".to_string()),
+            query: None,
+            ..Default::default()
+        };
+        let secret = secret("docker-creds", "kubernetes.io/dockerconfigjson", &[]);
+        assert!(!display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_docker_config_shown_with_show_all() {
+        let config = Config {
+            show_all: true,
+            namespace: Some("default".to_string()),
+            query: None,
+            ..Default::default()
+        };
+        let secret = secret("docker-creds", "kubernetes.io/dockerconfigjson", &[]);
+        assert!(display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_service_account_token_filtered() {
+        let config = Config {
+            show_all: false,
+            namespace: Some("default".to_string()),
+            query: None,
+            ..Default::default()
+        };
+        let secret = secret("sa-token", "kubernetes.io/service-account-token", &[]);
+        assert!(!display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_bootstrap_token_filtered() {
+        let config = Config {
+            show_all: false,
+            namespace: Some("default".to_string()),
+            query: None,
+            ..Default::default()
+        };
+        let secret = secret("bootstrap-token", "bootstrap.kubernetes.io/token", &[]);
+        assert!(!display_secret(&filter(&config), &secret));
+    }
+
+    // ============================================================================
+    // Type Filters
+    // ============================================================================
+
+    #[test]
+    fn test_display_secret_default_types() {
+        let f = filter(&Config::default());
+        assert!(display_secret(&f, &secret("db", "Opaque", &[])));
+        assert!(display_secret(
+            &f,
+            &secret("db", "kubernetes.io/basic-auth", &[])
+        ));
+        assert!(display_secret(
+            &f,
+            &secret("deploy-key", "kubernetes.io/ssh-auth", &[])
+        ));
+        assert!(!display_secret(
+            &f,
+            &secret("sh.helm.release.v1.web.v1", "helm.sh/release.v1", &[])
+        ));
+    }
+
+    #[test]
+    fn test_display_secret_missing_type_is_opaque() {
+        let secret = Secret {
+            metadata: ObjectMeta {
+                name: Some("untyped".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(display_secret(&filter(&Config::default()), &secret));
+
+        let config = Config {
+            types: vec!["kubernetes.io/tls".to_string()],
+            ..Default::default()
+        };
+        assert!(!display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_include_types() {
+        let config = Config {
+            types: vec![
+                "kubernetes.io/basic-auth".to_string(),
+                "kubernetes.io/tls".to_string(),
+            ],
+            ..Default::default()
+        };
+        let f = filter(&config);
+        assert!(display_secret(
+            &f,
+            &secret("db", "kubernetes.io/basic-auth", &[])
+        ));
+        assert!(display_secret(
+            &f,
+            &secret("cert", "kubernetes.io/tls", &[])
+        ));
+        assert!(!display_secret(&f, &secret("app", "Opaque", &[])));
+    }
+
+    #[test]
+    fn test_display_secret_exclude_types_only() {
+        let config = Config {
+            exclude_types: vec!["helm.sh/release.v1".to_string()],
+            ..Default::default()
+        };
+        let f = filter(&config);
+        assert!(display_secret(
+            &f,
+            &secret("cert", "kubernetes.io/tls", &[])
+        ));
+        assert!(display_secret(&f, &secret("app", "Opaque", &[])));
+        assert!(!display_secret(
+            &f,
+            &secret("sh.helm.release.v1.web.v1", "helm.sh/release.v1", &[])
+        ));
+    }
+
+    #[test]
+    fn test_display_secret_include_and_exclude_types() {
+        let config = Config {
+            types: vec!["Opaque".to_string(), "kubernetes.io/tls".to_string()],
+            exclude_types: vec!["kubernetes.io/tls".to_string()],
+            ..Default::default()
+        };
+        let f = filter(&config);
+        assert!(display_secret(&f, &secret("app", "Opaque", &[])));
+        assert!(!display_secret(
+            &f,
+            &secret("cert", "kubernetes.io/tls", &[])
+        ));
+    }
+
+    #[test]
+    fn test_display_secret_show_all_respects_exclusions() {
+        let config = Config {
+            show_all: true,
+            exclude_types: vec!["kubernetes.io/service-account-token".to_string()],
+            ..Default::default()
+        };
+        let f = filter(&config);
+        assert!(display_secret(
+            &f,
+            &secret("cert", "kubernetes.io/tls", &[])
+        ));
+        assert!(!display_secret(
+            &f,
+            &secret("sa-token", "kubernetes.io/service-account-token", &[])
+        ));
+    }
+
+    // ============================================================================
+    // Searching Keys and Values
+    // ============================================================================
+
+    #[test]
+    fn test_grep_matches_values() {
+        let config = Config {
+            grep: Some(r"db\.internal".to_string()),
+            ..Default::default()
+        };
+        let f = filter(&config);
+        assert!(display_secret(
+            &f,
+            &secret("app", "Opaque", &[("DATABASE_HOST", b"pg.db.internal")])
+        ));
+        assert!(!display_secret(
+            &f,
+            &secret("app", "Opaque", &[("DATABASE_HOST", b"pg.dbxinternal")])
+        ));
+        assert!(!display_secret(&f, &secret("empty", "Opaque", &[])));
+    }
+
+    #[test]
+    fn test_grep_matches_keys() {
+        let config = Config {
+            grep: Some("^password$".to_string()),
+            ..Default::default()
+        };
+        let f = filter(&config);
+        assert!(display_secret(
+            &f,
+            &secret("db", "Opaque", &[("password", b"x")])
+        ));
+        assert!(!display_secret(
+            &f,
+            &secret("db", "Opaque", &[("db-password", b"x")])
+        ));
+    }
+
+    #[test]
+    fn test_grep_ignore_case_and_binary_values() {
+        let config = Config {
+            grep: Some("admin".to_string()),
+            ignore_case: true,
+            ..Default::default()
+        };
+        let f = filter(&config);
+        assert!(display_secret(
+            &f,
+            &secret("db", "Opaque", &[("user", b"ADMIN")])
+        ));
+        assert!(!display_secret(
+            &f,
+            &secret("db", "Opaque", &[("store", &[0xff, b'a', b'd'])])
+        ));
+    }
+
+    #[test]
+    fn test_grep_combines_with_query() {
+        let config = Config {
+            query: Some("web".to_string()),
+            grep: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let f = filter(&config);
+        assert!(display_secret(
+            &f,
+            &secret("web-db", "Opaque", &[("user", b"admin")])
+        ));
+        assert!(!display_secret(
+            &f,
+            &secret("worker-db", "Opaque", &[("user", b"admin")])
+        ));
+    }
+
+    #[test]
+    fn test_retain_matching_keys() {
+        let config = Config {
+            grep: Some("admin".to_string()),
+            matching_keys: true,
+            ..Default::default()
+        };
+        let mut s = secret(
+            "db",
+            "Opaque",
+            &[
+                ("user", b"admin"),
+                ("password", b"hunter2"),
+                ("admin_email", b"x"),
+            ],
+        );
+        filter(&config).retain_matching_keys(&mut s);
+        let keys: Vec<&String> = s.data.as_ref().unwrap().keys().collect();
+        assert_eq!(keys, ["admin_email", "user"]);
+    }
+
+    #[test]
+    fn test_retain_matching_keys_disabled_by_default() {
+        let config = Config {
+            grep: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let mut s = secret(
+            "db",
+            "Opaque",
+            &[("user", b"admin"), ("password", b"hunter2")],
+        );
+        filter(&config).retain_matching_keys(&mut s);
+        assert_eq!(s.data.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_grep_is_an_error() {
+        let config = Config {
+            grep: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Filter::new(&config).unwrap_err().to_string(),
+            "Invalid --grep pattern '('"
+        );
+    }
+
+    // ============================================================================
+    // Query Modes
+    // ============================================================================
+
+    #[test]
+    fn test_display_secret_ignore_case_query() {
+        let config = Config {
+            query: Some("TOKEN".to_string()),
+            ignore_case: true,
+            ..Default::default()
+        };
+        let secret = secret("api-token", "Opaque", &[]);
+        assert!(display_secret(&filter(&config), &secret));
+    }
+
+    #[test]
+    fn test_display_secret_regex_query() {
+        let config = Config {
+            query: Some("^app-[a-z]+-db$".to_string()),
+            regex: true,
+            ..Default::default()
+        };
+        assert!(display_secret(
+            &filter(&config),
+            &secret("app-web-db", "Opaque", &[])
+        ));
+        assert!(!display_secret(
+            &filter(&config),
+            &secret("app-web-db-old", "Opaque", &[])
+        ));
+        assert!(!display_secret(
+            &filter(&config),
+            &secret("APP-web-db", "Opaque", &[])
+        ));
+    }
+
+    #[test]
+    fn test_display_secret_regex_ignore_case() {
+        let config = Config {
+            query: Some("^app-".to_string()),
+            regex: true,
+            ignore_case: true,
+            ..Default::default()
+        };
+        assert!(display_secret(
+            &filter(&config),
+            &secret("APP-web-db", "Opaque", &[])
+        ));
+    }
+
+    #[test]
+    fn test_display_secret_glob_query() {
+        let config = Config {
+            query: Some("app-*-db-credentials".to_string()),
+            glob: true,
+            ..Default::default()
+        };
+        assert!(display_secret(
+            &filter(&config),
+            &secret("app-web-db-credentials", "Opaque", &[])
+        ));
+        assert!(!display_secret(
+            &filter(&config),
+            &secret("my-app-web-db-credentials", "Opaque", &[])
+        ));
+        assert!(!display_secret(
+            &filter(&config),
+            &secret("app-web-db-credentials-v2", "Opaque", &[])
+        ));
+    }
+
+    #[test]
+    fn test_display_secret_glob_ignore_case() {
+        let config = Config {
+            query: Some("APP-*".to_string()),
+            glob: true,
+            ignore_case: true,
+            ..Default::default()
+        };
+        assert!(display_secret(
+            &filter(&config),
+            &secret("app-web", "Opaque", &[])
+        ));
+    }
+
+    // ============================================================================
+    // Server Side Selectors
+    // ============================================================================
+
+    #[test]
+    fn test_list_params_without_selectors() {
+        let params = filter(&Config::default()).list_params();
+        assert!(params.label_selector.is_none());
+        assert!(params.field_selector.is_none());
+    }
+
+    #[test]
+    fn test_list_params_with_selectors() {
+        let config = Config {
+            selector: Some("app=web,tier!=cache".to_string()),
+            field_selector: Some("metadata.name!=old".to_string()),
+            ..Default::default()
+        };
+        let params = filter(&config).list_params();
+        assert_eq!(
+            params.label_selector.as_deref(),
+            Some("app=web,tier!=cache")
+        );
+        assert_eq!(params.field_selector.as_deref(), Some("metadata.name!=old"));
+    }
+
+    #[test]
+    fn test_selectors_compose_with_query() {
+        let config = Config {
+            query: Some("db".to_string()),
+            selector: Some("app=web".to_string()),
+            ..Default::default()
+        };
+        // Selectors are applied by the API server, the query still filters locally
+        assert!(!display_secret(
+            &filter(&config),
+            &secret("web-cache", "Opaque", &[])
+        ));
+        assert!(display_secret(
+            &filter(&config),
+            &secret("web-db", "Opaque", &[])
+        ));
+    }
+
+    #[test]
+    fn test_invalid_regex_is_an_error() {
+        let config = Config {
+            query: Some("app-(".to_string()),
+            regex: true,
+            ..Default::default()
+        };
+        let err = Filter::new(&config).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid regular expression 'app-('");
+    }
+
+    #[test]
+    fn test_invalid_glob_is_an_error() {
+        let config = Config {
+            query: Some("app-[".to_string()),
+            glob: true,
+            ..Default::default()
+        };
+        assert!(Filter::new(&config).is_err());
+    }
+}
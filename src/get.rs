@@ -0,0 +1,67 @@
+use std::io::Write;
+
+use clap::Args;
+use k8s_openapi::api::core::v1::Secret;
+use kube::{Api, Client};
+
+#[derive(Args, Debug)]
+pub struct GetArgs {
+    namespace: String,
+    secret: String,
+    key: String,
+
+    /// Print a newline after the value
+    #[clap(short = 'n', long)]
+    newline: bool,
+}
+
+/// Write the decoded bytes of a single key to stdout, untouched
+pub async fn run(client: Client, args: &GetArgs) -> anyhow::Result<()> {
+    let api: Api<Secret> = Api::namespaced(client, &args.namespace);
+    let s = crate::fetch_secret(&api, &args.namespace, &args.secret).await?;
+    let value = lookup(&s, &args.key)?;
+
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(value)?;
+    if args.newline {
+        stdout.write_all(b"\n")?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+fn lookup<'a>(s: &'a Secret, key: &str) -> anyhow::Result<&'a [u8]> {
+    match s.data.as_ref().and_then(|data| data.get(key)) {
+        Some(value) => Ok(&value.0),
+        None => anyhow::bail!(
+            "Key '{}' not found in secret '{}'",
+            key,
+            s.metadata.name.as_deref().unwrap_or_default()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::secret;
+
+    #[test]
+    fn test_lookup_returns_raw_bytes() {
+        let s = secret("app", "Opaque", &[("keystore", &[0xfe, 0xed, 0x0a])]);
+        assert_eq!(lookup(&s, "keystore").unwrap(), [0xfe, 0xed, 0x0a]);
+    }
+
+    #[test]
+    fn test_lookup_missing_key() {
+        let s = secret("app", "Opaque", &[("keystore", &[0xfe, 0xed, 0x0a])]);
+        let err = lookup(&s, "password").unwrap_err();
+        assert_eq!(err.to_string(), "Key 'password' not found in secret 'app'");
+    }
+
+    #[test]
+    fn test_lookup_secret_without_data() {
+        let s = Secret::default();
+        assert!(lookup(&s, "password").is_err());
+    }
+}
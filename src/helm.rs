@@ -0,0 +1,159 @@
+//! Decoding the Helm releases stored in `helm.sh/release.v1` secrets
+//!
+//! Helm 3 keeps each revision of a release in its own secret, under the
+//! `release` key, as base64 encoded gzipped JSON.
+
+use std::io::Read;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The parts of a release worth showing
+#[derive(Debug, PartialEq)]
+pub struct Release {
+    pub name: String,
+    pub revision: i64,
+    pub chart: String,
+    pub chart_version: String,
+    pub app_version: Option<String>,
+    pub status: String,
+    /// When the revision was deployed, as written by Helm
+    pub deployed: Option<String>,
+    /// The values given when installing or upgrading, like `helm get values`
+    pub values: Value,
+}
+
+/// The most a release is decompressed to, well above what Helm could store in
+/// a 1MiB secret but small enough to stop a crafted gzip bomb
+const MAX_RELEASE_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct Payload {
+    name: String,
+    version: i64,
+    #[serde(default)]
+    info: Info,
+    chart: Chart,
+    #[serde(default)]
+    config: Value,
+}
+
+#[derive(Deserialize, Default)]
+struct Info {
+    status: Option<String>,
+    last_deployed: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chart {
+    metadata: ChartMetadata,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartMetadata {
+    name: String,
+    version: String,
+    app_version: Option<String>,
+}
+
+/// Decode the release held by a `release` key, `None` for other keys or
+/// values that aren't a Helm release
+pub fn parse(key: &str, value: &[u8]) -> Option<Release> {
+    if key != "release" {
+        return None;
+    }
+    let compressed = STANDARD.decode(value.trim_ascii()).ok()?;
+    let json = gunzip(&compressed, MAX_RELEASE_SIZE)?;
+    let payload: Payload = serde_json::from_slice(&json).ok()?;
+
+    Some(Release {
+        name: payload.name,
+        revision: payload.version,
+        chart: payload.chart.metadata.name,
+        chart_version: payload.chart.metadata.version,
+        app_version: payload.chart.metadata.app_version,
+        status: payload.info.status.unwrap_or_else(|| "unknown".to_string()),
+        deployed: payload.info.last_deployed,
+        values: payload.config,
+    })
+}
+
+/// Decompress gzipped data, `None` if it isn't gzip or is larger than `limit`
+fn gunzip(compressed: &[u8], limit: u64) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    GzDecoder::new(compressed)
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .ok()?;
+    (data.len() as u64 <= limit).then_some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    /// Encode a release the way Helm stores it
+    fn encode(json: &str) -> Vec<u8> {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(json.as_bytes()).unwrap();
+        STANDARD.encode(gz.finish().unwrap()).into_bytes()
+    }
+
+    const RELEASE: &str = r#"{
+        "name": "web",
+        "namespace": "default",
+        "version": 12,
+        "info": {"status": "deployed", "last_deployed": "2026-10-01T12:30:00.123456789Z"},
+        "chart": {"metadata": {"name": "nginx", "version": "15.1.0", "appVersion": "1.25.3"}},
+        "config": {"replicaCount": 3, "image": {"tag": "1.25.3"}},
+        "manifest": "---\n# Source: nginx/templates/deployment.yaml\n"
+    }"#;
+
+    #[test]
+    fn test_parse_release() {
+        let release = parse("release", &encode(RELEASE)).unwrap();
+        assert_eq!(
+            release,
+            Release {
+                name: "web".to_string(),
+                revision: 12,
+                chart: "nginx".to_string(),
+                chart_version: "15.1.0".to_string(),
+                app_version: Some("1.25.3".to_string()),
+                status: "deployed".to_string(),
+                deployed: Some("2026-10-01T12:30:00.123456789Z".to_string()),
+                values: serde_json::json!({"replicaCount": 3, "image": {"tag": "1.25.3"}}),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_release_without_values() {
+        let json = r#"{"name": "web", "version": 1, "chart": {"metadata": {"name": "nginx", "version": "1.0.0"}}}"#;
+        let release = parse("release", &encode(json)).unwrap();
+        assert_eq!(release.status, "unknown");
+        assert!(release.values.is_null());
+    }
+
+    #[test]
+    fn test_parse_not_a_release() {
+        assert!(parse("config", &encode(RELEASE)).is_none());
+        assert!(parse("release", b"hunter2").is_none());
+        assert!(parse("release", STANDARD.encode("not gzip").as_bytes()).is_none());
+    }
+
+    #[test]
+    fn test_gunzip_limit() {
+        let compressed = STANDARD.decode(encode(RELEASE)).unwrap();
+        let size = RELEASE.len() as u64;
+        assert_eq!(gunzip(&compressed, size).unwrap(), RELEASE.as_bytes());
+        assert!(gunzip(&compressed, size - 1).is_none());
+    }
+}
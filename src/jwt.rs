@@ -0,0 +1,131 @@
+//! Decoding JSON Web Tokens, such as service account tokens
+//!
+//! Only the header and claims are decoded, the signature isn't verified as
+//! that would need the issuer's keys.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The header fields and claims worth showing
+#[derive(Debug, PartialEq, Eq)]
+pub struct Token {
+    pub algorithm: String,
+    pub key_id: Option<String>,
+    pub issuer: Option<String>,
+    pub subject: Option<String>,
+    pub audience: Vec<String>,
+    /// Seconds since the epoch
+    pub issued_at: Option<i64>,
+    /// Seconds since the epoch, `None` for tokens that never expire
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    kid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    iss: Option<String>,
+    sub: Option<String>,
+    /// Either a single audience or a list of them
+    aud: Option<Value>,
+    iat: Option<i64>,
+    exp: Option<i64>,
+}
+
+/// Decode a value that looks like a JWT, `None` for anything else
+pub fn parse(value: &[u8]) -> Option<Token> {
+    let text = std::str::from_utf8(value).ok()?.trim();
+    let mut parts = text.split('.');
+    let (header, claims, _signature) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let header: Header = serde_json::from_slice(&decode(header)?).ok()?;
+    let claims: Claims = serde_json::from_slice(&decode(claims)?).ok()?;
+    let audience = match claims.aud {
+        Some(Value::String(aud)) => vec![aud],
+        Some(Value::Array(auds)) => auds
+            .into_iter()
+            .filter_map(|a| a.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Some(Token {
+        algorithm: header.alg,
+        key_id: header.kid.filter(|kid| !kid.is_empty()),
+        issuer: claims.iss,
+        subject: claims.sub,
+        audience,
+        issued_at: claims.iat,
+        expires_at: claims.exp,
+    })
+}
+
+/// JWTs use unpadded base64url, but be lenient about padding
+fn decode(part: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD.decode(part.trim_end_matches('=')).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(json: &str) -> String {
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    fn token(header: &str, claims: &str) -> String {
+        format!("{}.{}.c2lnbmF0dXJl", encode(header), encode(claims))
+    }
+
+    #[test]
+    fn test_parse_legacy_service_account_token() {
+        let value = token(
+            r#"{"alg":"RS256","kid":"abc"}"#,
+            r#"{"iss":"kubernetes/serviceaccount","sub":"system:serviceaccount:default:builder","kubernetes.io/serviceaccount/namespace":"default"}"#,
+        );
+        assert_eq!(
+            parse(format!("{}\n", value).as_bytes()).unwrap(),
+            Token {
+                algorithm: "RS256".to_string(),
+                key_id: Some("abc".to_string()),
+                issuer: Some("kubernetes/serviceaccount".to_string()),
+                subject: Some("system:serviceaccount:default:builder".to_string()),
+                audience: Vec::new(),
+                issued_at: None,
+                expires_at: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_audiences() {
+        let value = token(
+            r#"{"alg":"ES256"}"#,
+            r#"{"aud":["https://kubernetes.default.svc","vault"],"iat":1700000000,"exp":1700003600}"#,
+        );
+        let parsed = parse(value.as_bytes()).unwrap();
+        assert_eq!(parsed.audience, ["https://kubernetes.default.svc", "vault"]);
+        assert_eq!(parsed.issued_at, Some(1700000000));
+        assert_eq!(parsed.expires_at, Some(1700003600));
+
+        let value = token(r#"{"alg":"HS256"}"#, r#"{"aud":"api"}"#);
+        assert_eq!(parse(value.as_bytes()).unwrap().audience, ["api"]);
+    }
+
+    #[test]
+    fn test_parse_not_a_token() {
+        assert!(parse(b"hunter2").is_none());
+        assert!(parse(b"db.internal.example.com").is_none());
+        assert!(parse(token(r#"{"typ":"JWT"}"#, "{}").as_bytes()).is_none());
+        assert!(parse(format!("{}.extra", token(r#"{"alg":"none"}"#, "{}")).as_bytes()).is_none());
+    }
+}
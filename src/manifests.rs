@@ -0,0 +1,154 @@
+//! Loading `Secret` manifests from local YAML files
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use k8s_openapi::ByteString;
+use k8s_openapi::api::core::v1::Secret;
+use serde::Deserialize;
+
+/// Load every `Secret` from the given files and directories
+///
+/// Files may hold several YAML documents, documents of any other kind are
+/// skipped. Directories are searched for `.yaml`, `.yml` and `.json` files,
+/// but not recursively. `stringData` is merged into `data` the same way the
+/// API server does it.
+pub fn load(paths: &[PathBuf]) -> anyhow::Result<Vec<Secret>> {
+    let mut secrets = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            for file in manifest_files(path)? {
+                secrets.extend(load_file(&file)?);
+            }
+        } else {
+            secrets.extend(load_file(path)?);
+        }
+    }
+    Ok(secrets)
+}
+
+fn manifest_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("Unable to read directory '{}'", dir.display()))?
+    {
+        let path = entry?.path();
+        let is_manifest = path
+            .extension()
+            .is_some_and(|ext| ext == "yaml" || ext == "yml" || ext == "json");
+        if path.is_file() && is_manifest {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn load_file(path: &Path) -> anyhow::Result<Vec<Secret>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read '{}'", path.display()))?;
+    parse(&text).with_context(|| format!("Unable to parse '{}'", path.display()))
+}
+
+/// Parse the secrets out of a possibly multi document YAML string
+fn parse(text: &str) -> anyhow::Result<Vec<Secret>> {
+    let mut secrets = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(text) {
+        let value = serde_yaml::Value::deserialize(document)?;
+        if value.get("kind").and_then(|k| k.as_str()) != Some("Secret") {
+            continue;
+        }
+
+        let mut secret: Secret = serde_yaml::from_value(value)?;
+        if let Some(string_data) = secret.string_data.take() {
+            let data = secret.data.get_or_insert_with(Default::default);
+            for (key, value) in string_data {
+                data.insert(key, ByteString(value.into_bytes()));
+            }
+        }
+        secrets.push(secret);
+    }
+    Ok(secrets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFESTS: &str = r#"
+apiVersion: v1
+kind: Secret
+metadata:
+  name: db
+  namespace: app-prod
+type: Opaque
+data:
+  user: YXBw
+  password: b2xk
+stringData:
+  password: hunter2
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: settings
+data:
+  debug: "false"
+---
+apiVersion: v1
+kind: Secret
+metadata:
+  name: api
+stringData:
+  token: abc123
+"#;
+
+    fn value(s: &Secret, key: &str) -> Vec<u8> {
+        s.data.as_ref().unwrap()[key].0.clone()
+    }
+
+    #[test]
+    fn test_parse_multiple_documents() {
+        let secrets = parse(MANIFESTS).unwrap();
+        assert_eq!(secrets.len(), 2);
+        assert_eq!(secrets[0].metadata.name.as_deref(), Some("db"));
+        assert_eq!(secrets[0].metadata.namespace.as_deref(), Some("app-prod"));
+        assert_eq!(secrets[1].metadata.name.as_deref(), Some("api"));
+        assert!(secrets[1].metadata.namespace.is_none());
+    }
+
+    #[test]
+    fn test_parse_merges_string_data() {
+        let secrets = parse(MANIFESTS).unwrap();
+        assert_eq!(value(&secrets[0], "user"), b"app");
+        // stringData wins over data, as it does on the API server
+        assert_eq!(value(&secrets[0], "password"), b"hunter2");
+        assert_eq!(value(&secrets[1], "token"), b"abc123");
+        assert!(secrets[0].string_data.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_base64() {
+        let text = "apiVersion: v1\nkind: Secret\nmetadata:\n  name: x\ndata:\n  key: '!!'\n";
+        assert!(parse(text).is_err());
+    }
+
+    #[test]
+    fn test_load_directory() {
+        let dir =
+            std::env::temp_dir().join(format!("kube-secrets-manifests-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("secrets.yaml"), MANIFESTS).unwrap();
+        std::fs::write(dir.join("README.md"), "not a manifest").unwrap();
+
+        let secrets = load(std::slice::from_ref(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(secrets.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let err = load(&[PathBuf::from("/does/not/exist.yaml")]).unwrap_err();
+        assert_eq!(err.to_string(), "Unable to read '/does/not/exist.yaml'");
+    }
+}
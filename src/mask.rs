@@ -0,0 +1,187 @@
+//! Masking secret values so they can be listed without being shown
+//!
+//! Values are replaced by `****`, or with `--peek N` by their first and last
+//! `N` characters and their length, which is usually enough to tell which
+//! credential is which.
+
+use k8s_openapi::ByteString;
+use k8s_openapi::api::core::v1::Secret;
+use serde_json::Value;
+
+use crate::output::LAST_APPLIED_ANNOTATION;
+
+/// What every masked value, or the hidden part of it, is shown as
+pub const MASK: &str = "****";
+
+/// Mask a single value
+///
+/// Values too short to hide at least half of them, and values that aren't
+/// valid UTF-8, are masked completely.
+pub fn mask(value: &[u8], peek: Option<usize>) -> String {
+    let (Some(n), Ok(text)) = (peek.filter(|n| *n > 0), std::str::from_utf8(value)) else {
+        return MASK.to_string();
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() < n * 4 {
+        return MASK.to_string();
+    }
+    let first: String = chars[..n].iter().collect();
+    let last: String = chars[chars.len() - n..].iter().collect();
+    format!("{}{}{} ({} chars)", first, MASK, last, chars.len())
+}
+
+/// Copies of the secrets with every value masked
+///
+/// The last-applied-configuration annotation is dropped as well, since it
+/// holds the base64 encoded values of secrets created with `kubectl apply`.
+pub fn secrets(secrets: &[Secret], peek: Option<usize>) -> Vec<Secret> {
+    secrets
+        .iter()
+        .map(|s| {
+            let mut masked = s.clone();
+            for value in masked.data.iter_mut().flat_map(|data| data.values_mut()) {
+                *value = ByteString(mask(&value.0, peek).into_bytes());
+            }
+            let annotations = &mut masked.metadata.annotations;
+            if let Some(a) = annotations {
+                a.remove(LAST_APPLIED_ANNOTATION);
+            }
+            if annotations.as_ref().is_some_and(|a| a.is_empty()) {
+                *annotations = None;
+            }
+            masked
+        })
+        .collect()
+}
+
+/// A copy of a JSON document with every scalar masked, keeping only its keys
+/// and structure readable
+pub fn json(value: &Value, peek: Option<usize>) -> Value {
+    match value {
+        Value::String(s) => Value::String(mask(s.as_bytes(), peek)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| json(v, peek)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), json(v, peek)))
+                .collect(),
+        ),
+        scalar => Value::String(mask(scalar.to_string().as_bytes(), peek)),
+    }
+}
+
+/// A copy of a YAML document with every scalar masked, keeping its structure
+/// and its keys in order
+pub fn yaml(value: &serde_yaml::Value, peek: Option<usize>) -> serde_yaml::Value {
+    use serde_yaml::Value;
+
+    match value {
+        Value::String(s) => Value::String(mask(s.as_bytes(), peek)),
+        Value::Sequence(items) => Value::Sequence(items.iter().map(|v| yaml(v, peek)).collect()),
+        Value::Mapping(fields) => Value::Mapping(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), yaml(v, peek)))
+                .collect(),
+        ),
+        Value::Tagged(tagged) => {
+            let mut tagged = tagged.clone();
+            tagged.value = yaml(&tagged.value, peek);
+            Value::Tagged(tagged)
+        }
+        Value::Number(n) => Value::String(mask(n.to_string().as_bytes(), peek)),
+        Value::Bool(b) => Value::String(mask(b.to_string().as_bytes(), peek)),
+        Value::Null => Value::String(mask(b"null", peek)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(mask(b"hunter2", None), "****");
+        assert_eq!(mask(b"", None), "****");
+        assert_eq!(mask(b"hunter2", Some(0)), "****");
+    }
+
+    #[test]
+    fn test_mask_peek() {
+        assert_eq!(
+            mask(b"sk_live_abcdef123456", Some(3)),
+            "sk_****456 (20 chars)"
+        );
+        assert_eq!(mask("pässwörd".as_bytes(), Some(2)), "pä****rd (8 chars)");
+    }
+
+    #[test]
+    fn test_mask_peek_short_or_binary_values() {
+        assert_eq!(mask(b"hunter2", Some(2)), "****");
+        assert_eq!(mask(&[0xff; 32], Some(2)), "****");
+    }
+
+    #[test]
+    fn test_mask_json() {
+        let value = serde_json::json!({
+            "db": {"password": "hunter2", "pin": 5432, "tls": true, "ca": null},
+            "hosts": ["a", 1.5],
+        });
+        assert_eq!(
+            json(&value, None),
+            serde_json::json!({
+                "db": {"password": "****", "pin": "****", "tls": "****", "ca": "****"},
+                "hosts": ["****", "****"],
+            })
+        );
+        assert_eq!(
+            json(&serde_json::json!({"pin": 20240517}), Some(2)),
+            serde_json::json!({"pin": "20****17 (8 chars)"})
+        );
+    }
+
+    #[test]
+    fn test_mask_yaml_keeps_key_order() {
+        let value: serde_yaml::Value =
+            serde_yaml::from_str("user: app\npassword: hunter2\nport: 5432\nssl: true\n").unwrap();
+        assert_eq!(
+            serde_yaml::to_string(&yaml(&value, None)).unwrap(),
+            "user: '****'\npassword: '****'\nport: '****'\nssl: '****'\n"
+        );
+    }
+
+    #[test]
+    fn test_mask_secrets() {
+        let secret = Secret {
+            data: Some([("password".to_string(), ByteString(b"hunter2".to_vec()))].into()),
+            ..Default::default()
+        };
+        let masked = secrets(std::slice::from_ref(&secret), None);
+        assert_eq!(masked[0].data.as_ref().unwrap()["password"].0, b"****");
+        assert_eq!(secret.data.unwrap()["password"].0, b"hunter2");
+    }
+
+    #[test]
+    fn test_mask_secrets_drops_last_applied_configuration() {
+        let mut secret = Secret::default();
+        secret.metadata.annotations = Some(
+            [
+                (
+                    LAST_APPLIED_ANNOTATION.to_string(),
+                    r#"{"data":{"password":"aHVudGVyMg=="}}"#.to_string(),
+                ),
+                ("team".to_string(), "web".to_string()),
+            ]
+            .into(),
+        );
+        let masked = secrets(std::slice::from_ref(&secret), None);
+        let annotations = masked[0].metadata.annotations.as_ref().unwrap();
+        assert!(!annotations.contains_key(LAST_APPLIED_ANNOTATION));
+        assert_eq!(annotations["team"], "web");
+
+        secret.metadata.annotations.as_mut().unwrap().remove("team");
+        let masked = secrets(std::slice::from_ref(&secret), None);
+        assert!(masked[0].metadata.annotations.is_none());
+    }
+}
@@ -0,0 +1,118 @@
+//! Resolving the namespace argument, which may be a comma separated list of
+//! names and glob patterns such as `app-dev,app-prod` or `team-a-*`
+
+use anyhow::Context;
+use globset::{Glob, GlobMatcher};
+use k8s_openapi::api::core::v1::Namespace;
+use kube::{Api, Client};
+
+/// Whether the namespace argument can name more than one namespace
+pub fn is_pattern(arg: &str) -> bool {
+    arg.contains([',', '*', '?', '['])
+}
+
+/// Resolve the namespace argument against the namespaces in the cluster
+///
+/// Names in the list that don't exist are reported on stderr and skipped.
+pub async fn resolve(client: Client, arg: &str) -> anyhow::Result<Vec<String>> {
+    let patterns = compile(arg)?;
+    let api: Api<Namespace> = Api::all(client);
+    let existing: Vec<String> = api
+        .list(&Default::default())
+        .await?
+        .into_iter()
+        .filter_map(|n| n.metadata.name)
+        .collect();
+
+    for (part, _) in patterns.iter().filter(|(part, _)| !is_pattern(part)) {
+        if !existing.contains(part) {
+            eprintln!("Namespace '{}' does not exist", part);
+        }
+    }
+    Ok(matching(&patterns, &existing))
+}
+
+/// Split the argument into its parts, each compiled as a glob
+fn compile(arg: &str) -> anyhow::Result<Vec<(String, GlobMatcher)>> {
+    arg.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let glob =
+                Glob::new(part).with_context(|| format!("Invalid namespace pattern '{}'", part))?;
+            Ok((part.to_string(), glob.compile_matcher()))
+        })
+        .collect()
+}
+
+/// The existing namespaces matched by any of the patterns, in sorted order
+fn matching(patterns: &[(String, GlobMatcher)], existing: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = existing
+        .iter()
+        .filter(|name| {
+            patterns
+                .iter()
+                .any(|(_, glob)| glob.is_match(name.as_str()))
+        })
+        .cloned()
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn existing() -> Vec<String> {
+        [
+            "app-prod",
+            "app-dev",
+            "app-stage",
+            "team-a-web",
+            "team-a-jobs",
+            "team-b-web",
+        ]
+        .iter()
+        .map(|n| n.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_is_pattern() {
+        assert!(!is_pattern("default"));
+        assert!(is_pattern("app-dev,app-prod"));
+        assert!(is_pattern("team-a-*"));
+        assert!(is_pattern("app-[ds]*"));
+    }
+
+    #[test]
+    fn test_matching_list() {
+        let patterns = compile("app-prod, app-dev").unwrap();
+        assert_eq!(matching(&patterns, &existing()), ["app-dev", "app-prod"]);
+    }
+
+    #[test]
+    fn test_matching_glob() {
+        let patterns = compile("team-a-*").unwrap();
+        assert_eq!(
+            matching(&patterns, &existing()),
+            ["team-a-jobs", "team-a-web"]
+        );
+    }
+
+    #[test]
+    fn test_matching_list_of_globs_skips_missing() {
+        let patterns = compile("*-web,app-qa,app-prod").unwrap();
+        assert_eq!(
+            matching(&patterns, &existing()),
+            ["app-prod", "team-a-web", "team-b-web"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let err = compile("team-[a").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid namespace pattern 'team-[a'");
+    }
+}
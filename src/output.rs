@@ -0,0 +1,1103 @@
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use clap::ValueEnum;
+use colorful::Colorful;
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::jiff;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::Config;
+use crate::binary;
+use crate::docker;
+use crate::env;
+use crate::filter::{self, Filter};
+use crate::helm;
+use crate::jwt;
+use crate::mask;
+use crate::pretty;
+use crate::tls;
+
+/// Formats the selected secrets can be rendered in
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored listing meant for humans
+    #[default]
+    Text,
+    /// The colored listing with each secret's metadata, like `--show-metadata`
+    Wide,
+    /// A JSON array with one object per secret
+    Json,
+    /// `v1/Secret` manifests that can be re-applied with `kubectl apply -f`
+    Yaml,
+    /// `KEY=value` lines for a dotenv file
+    Env,
+    /// `export KEY='value'` lines to source in a shell
+    Export,
+}
+
+impl OutputFormat {
+    /// Whether this format is meant to be parsed by other programs, in which
+    /// case informational messages belong on stderr rather than stdout
+    pub fn is_structured(self) -> bool {
+        !matches!(self, OutputFormat::Text | OutputFormat::Wide)
+    }
+}
+
+/// Refuse to write masked values in a structured format to a file or pipe,
+/// where `****` would be taken for the real value by whatever reads it
+pub fn check_destination(c: &Config, to_terminal: bool) -> anyhow::Result<()> {
+    if !c.reveal && !to_terminal && c.output.is_structured() {
+        anyhow::bail!(
+            "Refusing to write masked values to a file or pipe, pass --reveal to write the real values"
+        );
+    }
+    Ok(())
+}
+
+/// JSON representation of a single secret
+///
+/// Values that are valid UTF-8 end up in `data`, anything else is kept
+/// base64 encoded in `binaryData`, mirroring how a ConfigMap splits them.
+/// Masked values stay where they were, so binary values can still be told
+/// apart.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SecretDocument<'a> {
+    name: &'a str,
+    namespace: Option<&'a str>,
+    #[serde(rename = "type")]
+    type_: Option<&'a str>,
+    data: BTreeMap<&'a str, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    binary_data: BTreeMap<&'a str, String>,
+}
+
+impl<'a> SecretDocument<'a> {
+    fn new(s: &'a Secret, reveal: bool, peek: Option<usize>) -> Self {
+        let mut data = BTreeMap::new();
+        let mut binary_data = BTreeMap::new();
+        for (key, value) in s.data.iter().flatten() {
+            let text = std::str::from_utf8(&value.0);
+            let shown = match text {
+                _ if !reveal => mask::mask(&value.0, peek),
+                Ok(text) => text.to_string(),
+                Err(_) => STANDARD.encode(&value.0),
+            };
+            if text.is_ok() {
+                data.insert(key.as_str(), shown);
+            } else {
+                binary_data.insert(key.as_str(), shown);
+            }
+        }
+
+        SecretDocument {
+            name: s.metadata.name.as_deref().unwrap_or_default(),
+            namespace: s.metadata.namespace.as_deref(),
+            type_: s.type_.as_deref(),
+            data,
+            binary_data,
+        }
+    }
+}
+
+/// Write the secrets to stdout in the format selected in the config, with
+/// their values masked unless `--reveal` was given
+pub fn render(c: &Config, f: &Filter, secrets: &[Secret]) -> anyhow::Result<()> {
+    // The listing masks values as it prints them so certificates can still
+    // be summarized and JSON masks them where they'd have gone, the other
+    // formats get masked copies
+    let masked;
+    let shown = if c.reveal || !c.output.is_structured() {
+        secrets
+    } else {
+        masked = mask::secrets(secrets, c.peek);
+        &masked
+    };
+    if !c.reveal && c.output.is_structured() && !secrets.is_empty() {
+        eprintln!("Values are masked, pass --reveal to show them");
+    }
+
+    match c.output {
+        OutputFormat::Text | OutputFormat::Wide => print_text(c, f.grep(), secrets),
+        OutputFormat::Json => println!("{}", to_json(secrets, c.reveal, c.peek)?),
+        OutputFormat::Yaml => print!("{}", to_yaml(shown, c.keep_metadata)?),
+        OutputFormat::Env => print!("{}", to_env(shown, false, c.spans_namespaces())?),
+        OutputFormat::Export => print!("{}", to_env(shown, true, c.spans_namespaces())?),
+    }
+    Ok(())
+}
+
+/// Seconds since the epoch
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Format seconds since the epoch as an RFC 3339 timestamp in UTC
+pub fn format_time(seconds: i64) -> String {
+    jiff::Timestamp::from_second(seconds)
+        .map(|t| t.to_string())
+        .unwrap_or_else(|_| seconds.to_string())
+}
+
+/// Print the colored listing, with a header for each namespace when the
+/// secrets come from more than one
+///
+/// Values holding certificates or private keys are summarized rather than
+/// printed, see [`tls_lines`], docker configs are split up by registry, see
+/// [`docker_lines`], and Helm releases are decoded, see [`helm_lines`]. Unless
+/// `--raw` is given JSON, YAML and base64 values are pretty printed, see
+/// [`pretty::render`]. Tokens are decoded below their value, see [`jwt_lines`],
+/// and binary values are described rather than printed, see [`binary_lines`].
+fn print_text(c: &Config, grep: Option<&Regex>, secrets: &[Secret]) {
+    let show_metadata = c.show_metadata || c.output == OutputFormat::Wide;
+    let now = now();
+    let mut current_namespace = None;
+    for s in secrets {
+        if c.spans_namespaces() && current_namespace != Some(&s.metadata.namespace) {
+            current_namespace = Some(&s.metadata.namespace);
+            let namespace = s.metadata.namespace.clone().unwrap_or_default();
+            println!("{}", format!("Namespace {}", namespace).yellow().bold());
+            println!();
+        }
+        println!(
+            "{}:",
+            s.metadata.name.clone().unwrap_or_default().light_blue()
+        );
+        if show_metadata {
+            for line in metadata_lines(s, now) {
+                println!("  {}", line.dim());
+            }
+        }
+
+        let data = s.data.clone().unwrap_or_default();
+        let bundles: BTreeMap<&String, tls::Bundle> = data
+            .iter()
+            .filter_map(|(key, value)| Some((key, tls::parse(&value.0)?)))
+            .collect();
+        let certificates: Vec<&tls::Certificate> =
+            bundles.values().flat_map(|b| &b.certificates).collect();
+
+        for (key, value) in data.iter() {
+            let colored_key = highlight(key, grep, |k| k.light_green().to_string());
+            if let Some(bundle) = bundles.get(key) {
+                println!("  {}:", colored_key);
+                for line in tls_lines(bundle, &certificates, now) {
+                    println!("    {}", line);
+                }
+                continue;
+            }
+            if let Some(release) = helm::parse(key, &value.0) {
+                println!("  {}:", colored_key);
+                for line in helm_lines(&release, c) {
+                    println!("    {}", line);
+                }
+                continue;
+            }
+            if let Some(credentials) = docker::parse(key, &value.0) {
+                println!("  {}:", colored_key);
+                for line in docker_lines(&credentials, c.reveal, c.peek) {
+                    println!("    {}", line);
+                }
+                continue;
+            }
+
+            if !c.raw
+                && let Some(pretty) = pretty::render(&value.0, c.reveal, c.peek)
+            {
+                let note = if pretty.base64 {
+                    " (base64)".dim().to_string()
+                } else {
+                    String::new()
+                };
+                println!("  {}:{}", colored_key, note);
+                for line in pretty.lines {
+                    println!("    {}", highlight(&line, grep, str::to_string));
+                }
+                continue;
+            }
+
+            match std::str::from_utf8(&value.0) {
+                Ok(_) if !c.reveal => {
+                    let masked = mask::mask(&value.0, c.peek);
+                    println!(
+                        "  {}: {}",
+                        colored_key,
+                        highlight(&masked, grep, str::to_string)
+                    )
+                }
+                Ok(text) => println!(
+                    "  {}: {}",
+                    colored_key,
+                    highlight(text, grep, str::to_string)
+                ),
+                Err(_) => {
+                    println!("  {}:", colored_key);
+                    for line in binary_lines(&value.0, c.hexdump && c.reveal) {
+                        println!("    {}", line);
+                    }
+                }
+            }
+            if let Some(token) = jwt::parse(&value.0) {
+                for line in jwt_lines(&token, now) {
+                    println!("    {}", line.dim());
+                }
+            }
+        }
+        println!()
+    }
+}
+
+/// Summarize the certificates and private keys of a value. Keys are checked
+/// against every certificate in the secret, as they're usually stored under
+/// a key of their own.
+fn tls_lines(bundle: &tls::Bundle, certificates: &[&tls::Certificate], now: i64) -> Vec<String> {
+    let mut lines = Vec::new();
+    for cert in &bundle.certificates {
+        let days = cert.days_until_expiry(now);
+        let expiry = match days {
+            d if d < 0 => format!("expired {} days ago", -d).red().to_string(),
+            d if d < EXPIRY_WARNING_DAYS => format!("in {} days", d).yellow().to_string(),
+            d => format!("in {} days", d),
+        };
+        let sans = if cert.sans.is_empty() {
+            "<none>".to_string()
+        } else {
+            cert.sans.join(", ")
+        };
+        lines.push(format!("Certificate {}", cert.subject));
+        lines.push(format!("  {:9}{}", "SANs:", sans));
+        lines.push(format!("  {:9}{}", "Issuer:", cert.issuer));
+        lines.push(format!("  {:9}{}", "Serial:", cert.serial));
+        lines.push(format!(
+            "  {:9}{} to {}",
+            "Valid:",
+            format_time(cert.not_before),
+            format_time(cert.not_after)
+        ));
+        lines.push(format!("  {:9}{}", "Expires:", expiry));
+    }
+    for key in &bundle.keys {
+        let matching = certificates
+            .iter()
+            .map(|cert| key.matches(cert).map(|m| (m, cert)))
+            .collect::<Option<Vec<_>>>();
+        let status = match matching {
+            _ if certificates.is_empty() => String::new(),
+            None => ", can't tell which certificate it belongs to".to_string(),
+            Some(m) => match m.iter().find(|(matches, _)| *matches) {
+                Some((_, cert)) => format!(", matches {}", cert.subject),
+                None => ", doesn't match any certificate".red().to_string(),
+            },
+        };
+        lines.push(format!("{} private key{}", key.kind, status));
+    }
+    if bundle.unreadable > 0 {
+        lines.push(format!("{} unreadable PEM blocks", bundle.unreadable));
+    }
+    lines
+}
+
+/// Summarize a value that isn't text by its size, format and SHA-256, which
+/// don't give away its contents, along with a hex dump when asked for
+fn binary_lines(value: &[u8], hexdump: bool) -> Vec<String> {
+    let mut lines = vec![
+        format!("{}, {} bytes", binary::kind(value), value.len()),
+        format!("SHA-256: {}", binary::sha256(value)),
+    ];
+    if hexdump {
+        lines.extend(
+            binary::hexdump(value, binary::HEXDUMP_BYTES)
+                .into_iter()
+                .map(|l| l.dim().to_string()),
+        );
+    }
+    lines
+}
+
+/// Describe a Helm release, along with its values when asked for. String
+/// values are masked unless values are revealed.
+fn helm_lines(release: &helm::Release, c: &Config) -> Vec<String> {
+    let chart = match &release.app_version {
+        Some(app) => format!("{} {} (app {})", release.chart, release.chart_version, app),
+        None => format!("{} {}", release.chart, release.chart_version),
+    };
+    let deployed = match &release.deployed {
+        Some(d) => match d.parse::<jiff::Timestamp>() {
+            Ok(t) => format_time(t.as_second()),
+            Err(_) => d.clone(),
+        },
+        None => "<unknown>".to_string(),
+    };
+
+    let mut lines = vec![
+        format!(
+            "{:10}{} (revision {})",
+            "Release:", release.name, release.revision
+        ),
+        format!("{:10}{}", "Chart:", chart),
+        format!("{:10}{}", "Status:", release.status),
+        format!("{:10}{}", "Deployed:", deployed),
+    ];
+    if c.helm_values {
+        let empty = release.values.as_object().is_none_or(|v| v.is_empty());
+        if empty {
+            lines.push(format!("{:10}<none>", "Values:"));
+        } else {
+            let values = if c.reveal {
+                release.values.clone()
+            } else {
+                mask::json(&release.values, c.peek)
+            };
+            lines.push("Values:".to_string());
+            let yaml = serde_yaml::to_string(&values).unwrap_or_default();
+            lines.extend(yaml.lines().map(|line| format!("  {}", line)));
+        }
+    }
+    lines
+}
+
+/// Show the header and claims of a token. Tokens without an expiry, like
+/// legacy service account tokens, are pointed out.
+fn jwt_lines(token: &jwt::Token, now: i64) -> Vec<String> {
+    let algorithm = match &token.key_id {
+        Some(kid) => format!("{} (key {})", token.algorithm, kid),
+        None => token.algorithm.clone(),
+    };
+    let expires = match token.expires_at {
+        Some(exp) if exp < now => format!("{}, expired {} ago", format_time(exp), age(now - exp)),
+        Some(exp) => format!("{}, in {}", format_time(exp), age(exp - now)),
+        None => "never".to_string(),
+    };
+    let audience = if token.audience.is_empty() {
+        "<none>".to_string()
+    } else {
+        token.audience.join(", ")
+    };
+
+    let mut lines = vec![
+        format!("{:11}{}", "Algorithm:", algorithm),
+        format!(
+            "{:11}{}",
+            "Issuer:",
+            token.issuer.as_deref().unwrap_or("<none>")
+        ),
+        format!(
+            "{:11}{}",
+            "Subject:",
+            token.subject.as_deref().unwrap_or("<none>")
+        ),
+        format!("{:11}{}", "Audience:", audience),
+    ];
+    if let Some(iat) = token.issued_at {
+        lines.push(format!("{:11}{}", "Issued:", format_time(iat)));
+    }
+    lines.push(format!("{:11}{}", "Expires:", expires));
+    lines
+}
+
+/// Show the credentials of each registry, masking the password unless
+/// values are revealed
+fn docker_lines(
+    credentials: &[docker::Credential],
+    reveal: bool,
+    peek: Option<usize>,
+) -> Vec<String> {
+    let none = || "<none>".to_string();
+    let mut lines = Vec::new();
+    for c in credentials {
+        let password = match &c.password {
+            Some(password) if reveal => password.clone(),
+            Some(password) => mask::mask(password.as_bytes(), peek),
+            None => none(),
+        };
+        lines.push(format!("Registry {}", c.registry));
+        lines.push(format!(
+            "  {:10}{}",
+            "Username:",
+            c.username.clone().unwrap_or_else(none)
+        ));
+        lines.push(format!("  {:10}{}", "Password:", password));
+        if let Some(email) = &c.email {
+            lines.push(format!("  {:10}{}", "Email:", email));
+        }
+    }
+    if lines.is_empty() {
+        lines.push("No registries".to_string());
+    }
+    lines
+}
+
+/// Annotation `kubectl apply` stores the last applied manifest in, including
+/// the base64 encoded data of secrets created that way
+pub const LAST_APPLIED_ANNOTATION: &str = "kubectl.kubernetes.io/last-applied-configuration";
+
+/// Certificates expiring within this many days are highlighted
+const EXPIRY_WARNING_DAYS: i64 = 30;
+
+/// Annotations that are never shown in full, as they're long and may hold a
+/// copy of the secret's data
+const COLLAPSED_ANNOTATIONS: &[&str] = &[LAST_APPLIED_ANNOTATION];
+
+/// Annotation values longer than this are collapsed too
+const MAX_ANNOTATION_LENGTH: usize = 100;
+
+/// Describe the secret's metadata in the style of `kubectl describe`, with
+/// long annotations collapsed to their size
+fn metadata_lines(s: &Secret, now: i64) -> Vec<String> {
+    let created = s
+        .metadata
+        .creation_timestamp
+        .as_ref()
+        .map(|t| t.0.as_second());
+    let labels: Vec<String> = s
+        .metadata
+        .labels
+        .iter()
+        .flatten()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    let annotations: Vec<String> = s
+        .metadata
+        .annotations
+        .iter()
+        .flatten()
+        .map(|(key, value)| {
+            if COLLAPSED_ANNOTATIONS.contains(&key.as_str())
+                || value.len() > MAX_ANNOTATION_LENGTH
+                || value.contains('\n')
+            {
+                format!("{}=<{} bytes>", key, value.len())
+            } else {
+                format!("{}={}", key, value)
+            }
+        })
+        .collect();
+    let owners: Vec<String> = s
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .map(|owner| {
+            let controller = if owner.controller == Some(true) {
+                " (controller)"
+            } else {
+                ""
+            };
+            format!("{}/{}{}", owner.kind, owner.name, controller)
+        })
+        .collect();
+
+    let mut lines = vec![
+        format!("{:13}{}", "Type:", filter::secret_type(s)),
+        format!(
+            "{:13}{}",
+            "Age:",
+            created
+                .map(|t| age(now - t))
+                .unwrap_or_else(|| "<unknown>".to_string())
+        ),
+        format!("{:13}{}", "Immutable:", s.immutable.unwrap_or_default()),
+    ];
+    for (field, values) in [
+        ("Labels:", labels),
+        ("Annotations:", annotations),
+        ("Owners:", owners),
+    ] {
+        if values.is_empty() {
+            lines.push(format!("{:13}<none>", field));
+        }
+        for (i, value) in values.iter().enumerate() {
+            lines.push(format!("{:13}{}", if i == 0 { field } else { "" }, value));
+        }
+    }
+    lines
+}
+
+/// Short human readable age, like the AGE column of `kubectl get`
+fn age(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    match seconds {
+        s if s < 120 => format!("{}s", s),
+        s if s < 2 * 3600 => format!("{}m", s / 60),
+        s if s < 48 * 3600 => format!("{}h", s / 3600),
+        s if s < 730 * 86400 => format!("{}d", s / 86400),
+        s => format!("{}y", s / (365 * 86400)),
+    }
+}
+
+/// Highlight the parts of `text` matching the `--grep` pattern, painting the
+/// rest with `plain`
+fn highlight(text: &str, grep: Option<&Regex>, plain: impl Fn(&str) -> String) -> String {
+    let Some(re) = grep else {
+        return plain(text);
+    };
+
+    let mut out = String::new();
+    let mut last = 0;
+    for m in re.find_iter(text).filter(|m| !m.is_empty()) {
+        if m.start() > last {
+            out.push_str(&plain(&text[last..m.start()]));
+        }
+        out.push_str(&m.as_str().red().bold().to_string());
+        last = m.end();
+    }
+    if last < text.len() {
+        out.push_str(&plain(&text[last..]));
+    }
+    out
+}
+
+fn to_json(secrets: &[Secret], reveal: bool, peek: Option<usize>) -> anyhow::Result<String> {
+    let documents: Vec<SecretDocument> = secrets
+        .iter()
+        .map(|s| SecretDocument::new(s, reveal, peek))
+        .collect();
+    Ok(serde_json::to_string_pretty(&documents)?)
+}
+
+/// Turn a secret as returned by the API into a manifest that can be edited
+/// and re-applied
+///
+/// Decoded values are moved to `stringData` so they're readable, only values
+/// that aren't valid UTF-8 are left base64 encoded in `data`.
+fn to_manifest(s: &Secret, keep_metadata: bool) -> Secret {
+    let mut manifest = s.clone();
+    let mut string_data = BTreeMap::new();
+    let mut data = BTreeMap::new();
+    for (key, value) in s.data.iter().flatten() {
+        match std::str::from_utf8(&value.0) {
+            Ok(text) => {
+                string_data.insert(key.clone(), text.to_string());
+            }
+            Err(_) => {
+                data.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    manifest.string_data = (!string_data.is_empty()).then_some(string_data);
+    manifest.data = (!data.is_empty()).then_some(data);
+
+    if !keep_metadata {
+        let metadata = &mut manifest.metadata;
+        metadata.uid = None;
+        metadata.resource_version = None;
+        metadata.managed_fields = None;
+        metadata.creation_timestamp = None;
+        metadata.generation = None;
+        metadata.self_link = None;
+        // kubectl writes this again when the manifest is applied
+        if let Some(annotations) = &mut metadata.annotations {
+            annotations.remove(LAST_APPLIED_ANNOTATION);
+        }
+        if metadata.annotations.as_ref().is_some_and(|a| a.is_empty()) {
+            metadata.annotations = None;
+        }
+    }
+    manifest
+}
+
+fn to_yaml(secrets: &[Secret], keep_metadata: bool) -> anyhow::Result<String> {
+    let mut out = String::new();
+    for s in secrets {
+        out.push_str("---\n");
+        out.push_str(&serde_yaml::to_string(&to_manifest(s, keep_metadata))?);
+    }
+    Ok(out)
+}
+
+/// Render every key as an environment variable assignment, either dotenv
+/// style or as shell `export` statements
+///
+/// Keys are converted with [`env::var_names`], values that aren't valid UTF-8
+/// can't be put in the environment and are skipped with a warning.
+fn to_env(secrets: &[Secret], export: bool, qualify_names: bool) -> anyhow::Result<String> {
+    let mut out = String::new();
+    for s in secrets {
+        let secret_name = s.metadata.name.as_deref().unwrap_or_default();
+        if qualify_names {
+            let namespace = s.metadata.namespace.as_deref().unwrap_or_default();
+            out.push_str(&format!("# {}/{}\n", namespace, secret_name));
+        } else {
+            out.push_str(&format!("# {}\n", secret_name));
+        }
+        let data = s.data.clone().unwrap_or_default();
+        let names = env::var_names(secret_name, data.keys().map(String::as_str))?;
+        for (key, value) in data.iter() {
+            let Ok(value) = std::str::from_utf8(&value.0) else {
+                eprintln!("Skipping {}/{}: value is not valid UTF-8", secret_name, key);
+                continue;
+            };
+            let name = &names[key.as_str()];
+            if name != key {
+                eprintln!("Renamed {}/{} to {}", secret_name, key, name);
+            }
+            if export {
+                out.push_str(&format!("export {}={}\n", name, env::shell_quote(value)));
+            } else {
+                out.push_str(&format!("{}={}\n", name, env::dotenv_quote(value)));
+            }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{ISSUED, OTHER_RSA_KEY, RSA_CERT, RSA_KEY, secret};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{OwnerReference, Time};
+    use serde::Deserialize;
+
+    #[test]
+    fn test_json_contains_metadata_and_values() {
+        let secrets = [secret(
+            "db",
+            "Opaque",
+            &[("user", b"admin"), ("password", b"hunter2")],
+        )];
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&secrets, true, None).unwrap()).unwrap();
+        assert_eq!(json[0]["name"], "db");
+        assert_eq!(json[0]["namespace"], "default");
+        assert_eq!(json[0]["type"], "Opaque");
+        assert_eq!(json[0]["data"]["user"], "admin");
+        assert_eq!(json[0]["data"]["password"], "hunter2");
+        assert!(json[0].get("binaryData").is_none());
+    }
+
+    #[test]
+    fn test_json_marks_binary_values() {
+        let secrets = [secret(
+            "keystore",
+            "Opaque",
+            &[("store.jks", &[0xfe, 0xed, 0xfe, 0xed])],
+        )];
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&secrets, true, None).unwrap()).unwrap();
+        assert!(json[0]["data"].get("store.jks").is_none());
+        assert_eq!(json[0]["binaryData"]["store.jks"], "/u3+7Q==");
+    }
+
+    #[test]
+    fn test_json_masks_values_in_place() {
+        let secrets = [secret(
+            "keystore",
+            "Opaque",
+            &[
+                ("password", b"hunter2"),
+                ("store.jks", &[0xfe, 0xed, 0xfe, 0xed]),
+            ],
+        )];
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&secrets, false, None).unwrap()).unwrap();
+        assert_eq!(json[0]["data"]["password"], "****");
+        assert!(json[0]["data"].get("store.jks").is_none());
+        assert_eq!(json[0]["binaryData"]["store.jks"], "****");
+    }
+
+    #[test]
+    fn test_json_empty_list() {
+        assert_eq!(to_json(&[], false, None).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_format_is_structured() {
+        assert!(!OutputFormat::Text.is_structured());
+        assert!(!OutputFormat::Wide.is_structured());
+        assert!(OutputFormat::Json.is_structured());
+        assert!(OutputFormat::Yaml.is_structured());
+        assert!(OutputFormat::Env.is_structured());
+        assert!(OutputFormat::Export.is_structured());
+    }
+
+    #[test]
+    fn test_check_destination() {
+        let config = |output, reveal| Config {
+            output,
+            reveal,
+            ..Default::default()
+        };
+        for format in [
+            OutputFormat::Json,
+            OutputFormat::Yaml,
+            OutputFormat::Env,
+            OutputFormat::Export,
+        ] {
+            assert!(check_destination(&config(format, false), false).is_err());
+            assert!(check_destination(&config(format, false), true).is_ok());
+            assert!(check_destination(&config(format, true), false).is_ok());
+        }
+        assert!(check_destination(&config(OutputFormat::Text, false), false).is_ok());
+    }
+
+    #[test]
+    fn test_env_output() {
+        let secrets = [secret(
+            "app",
+            "Opaque",
+            &[
+                ("DB_PASSWORD", b"it's"),
+                ("api-key", b"abc123"),
+                ("bin", &[0xff]),
+            ],
+        )];
+        assert_eq!(
+            to_env(&secrets, false, false).unwrap(),
+            "# app\nDB_PASSWORD=\"it's\"\napi_key=abc123\n\n"
+        );
+    }
+
+    #[test]
+    fn test_export_output() {
+        let secrets = [secret(
+            "app",
+            "Opaque",
+            &[("DB_PASSWORD", b"it's"), ("api-key", b"abc123")],
+        )];
+        assert_eq!(
+            to_env(&secrets, true, false).unwrap(),
+            "# app\nexport DB_PASSWORD='it'\\''s'\nexport api_key='abc123'\n\n"
+        );
+    }
+
+    #[test]
+    fn test_env_output_qualified_names() {
+        let secrets = [secret("app", "Opaque", &[("user", b"admin")])];
+        assert_eq!(
+            to_env(&secrets, false, true).unwrap(),
+            "# default/app\nuser=admin\n\n"
+        );
+    }
+
+    #[test]
+    fn test_env_output_rejects_colliding_names() {
+        let secrets = [secret(
+            "app",
+            "Opaque",
+            &[("api-key", b"a"), ("api_key", b"b")],
+        )];
+        let err = to_env(&secrets, false, false).unwrap_err();
+        assert!(err.to_string().contains("'api_key'"));
+    }
+
+    #[test]
+    fn test_highlight_without_pattern() {
+        assert_eq!(
+            highlight("db.internal", None, |t| format!("<{}>", t)),
+            "<db.internal>"
+        );
+    }
+
+    #[test]
+    fn test_highlight_marks_matches() {
+        let re = Regex::new("db").unwrap();
+        let out = highlight("pg.db.internal/db", Some(&re), |t| format!("<{}>", t));
+        let marked = "db".red().bold().to_string();
+        assert_eq!(out, format!("<pg.>{m}<.internal/>{m}", m = marked));
+    }
+
+    #[test]
+    fn test_tls_lines() {
+        let bundle = tls::parse(RSA_CERT.as_bytes()).unwrap();
+        let certificates: Vec<&tls::Certificate> = bundle.certificates.iter().collect();
+        let lines = tls_lines(&bundle, &certificates, ISSUED);
+        assert_eq!(
+            lines[..5],
+            [
+                "Certificate CN=example.com, O=Acme",
+                "  SANs:    DNS:example.com, DNS:www.example.com, IP:10.0.0.1",
+                "  Issuer:  CN=example.com, O=Acme",
+                "  Serial:  12:34:ab:cd",
+                "  Valid:   2026-10-16T20:51:43Z to 2027-10-16T20:51:43Z",
+            ]
+        );
+        assert_eq!(lines[5], "  Expires: in 365 days");
+        assert!(lines[5..].iter().all(|l| !l.contains("BEGIN")));
+
+        let expired = tls_lines(&bundle, &certificates, ISSUED + 400 * 86400);
+        assert!(expired[5].contains("expired 35 days ago"));
+
+        let key_lines = tls_lines(
+            &tls::parse(RSA_KEY.as_bytes()).unwrap(),
+            &certificates,
+            ISSUED,
+        );
+        assert_eq!(
+            key_lines,
+            ["RSA 2048 bit private key, matches CN=example.com, O=Acme"]
+        );
+        let other_lines = tls_lines(
+            &tls::parse(OTHER_RSA_KEY.as_bytes()).unwrap(),
+            &certificates,
+            ISSUED,
+        );
+        assert!(other_lines[0].contains("doesn't match any certificate"));
+        assert!(!other_lines[0].contains("MII"));
+    }
+
+    #[test]
+    fn test_docker_lines() {
+        let credentials = [docker::Credential {
+            registry: "ghcr.io".to_string(),
+            username: Some("bob".to_string()),
+            password: Some("hunter2".to_string()),
+            email: None,
+        }];
+        assert_eq!(
+            docker_lines(&credentials, false, None),
+            ["Registry ghcr.io", "  Username: bob", "  Password: ****"]
+        );
+        assert_eq!(
+            docker_lines(&credentials, true, None)[2],
+            "  Password: hunter2"
+        );
+        assert_eq!(docker_lines(&[], false, None), ["No registries"]);
+    }
+
+    #[test]
+    fn test_jwt_lines() {
+        let token = jwt::Token {
+            algorithm: "RS256".to_string(),
+            key_id: Some("abc".to_string()),
+            issuer: Some("https://kubernetes.default.svc".to_string()),
+            subject: Some("system:serviceaccount:default:builder".to_string()),
+            audience: vec!["https://kubernetes.default.svc".to_string()],
+            issued_at: Some(0),
+            expires_at: Some(3600),
+        };
+        assert_eq!(
+            jwt_lines(&token, 600),
+            [
+                "Algorithm: RS256 (key abc)",
+                "Issuer:    https://kubernetes.default.svc",
+                "Subject:   system:serviceaccount:default:builder",
+                "Audience:  https://kubernetes.default.svc",
+                "Issued:    1970-01-01T00:00:00Z",
+                "Expires:   1970-01-01T01:00:00Z, in 50m",
+            ]
+        );
+        assert_eq!(
+            jwt_lines(&token, 7200)[5],
+            "Expires:   1970-01-01T01:00:00Z, expired 60m ago"
+        );
+
+        let legacy = jwt::Token {
+            key_id: None,
+            audience: Vec::new(),
+            issued_at: None,
+            expires_at: None,
+            ..token
+        };
+        let lines = jwt_lines(&legacy, 0);
+        assert_eq!(lines[0], "Algorithm: RS256");
+        assert_eq!(lines[3], "Audience:  <none>");
+        assert_eq!(lines[4], "Expires:   never");
+    }
+
+    fn release() -> helm::Release {
+        helm::Release {
+            name: "web".to_string(),
+            revision: 12,
+            chart: "nginx".to_string(),
+            chart_version: "15.1.0".to_string(),
+            app_version: Some("1.25.3".to_string()),
+            status: "deployed".to_string(),
+            deployed: Some("2026-10-01T12:30:00.123456789Z".to_string()),
+            values: serde_json::json!({
+                "replicaCount": 3,
+                "auth": {"enabled": true, "password": "hunter2"},
+            }),
+        }
+    }
+
+    #[test]
+    fn test_helm_lines() {
+        assert_eq!(
+            helm_lines(&release(), &Config::default()),
+            [
+                "Release:  web (revision 12)",
+                "Chart:    nginx 15.1.0 (app 1.25.3)",
+                "Status:   deployed",
+                "Deployed: 2026-10-01T12:30:00Z",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_helm_lines_values() {
+        let config = Config {
+            helm_values: true,
+            ..Default::default()
+        };
+        let lines = helm_lines(&release(), &config);
+        assert_eq!(
+            lines[4..],
+            [
+                "Values:",
+                "  auth:",
+                "    enabled: '****'",
+                "    password: '****'",
+                "  replicaCount: '****'"
+            ]
+        );
+
+        let config = Config {
+            helm_values: true,
+            reveal: true,
+            ..Default::default()
+        };
+        assert!(helm_lines(&release(), &config).contains(&"    password: hunter2".to_string()));
+
+        let mut release = release();
+        release.values = serde_json::Value::Null;
+        assert_eq!(helm_lines(&release, &config)[4], "Values:   <none>");
+    }
+
+    #[test]
+    fn test_age() {
+        assert_eq!(age(45), "45s");
+        assert_eq!(age(600), "10m");
+        assert_eq!(age(5 * 3600), "5h");
+        assert_eq!(age(3 * 86400), "3d");
+        assert_eq!(age(800 * 86400), "2y");
+        assert_eq!(age(-5), "0s");
+    }
+
+    #[test]
+    fn test_metadata_lines() {
+        let mut s = live_secret();
+        s.immutable = Some(true);
+        s.metadata.annotations = Some(
+            [
+                ("meta.helm.sh/release-name".to_string(), "web".to_string()),
+                (
+                    "kubectl.kubernetes.io/last-applied-configuration".to_string(),
+                    r#"{"data":{"password":"aHVudGVyMg=="}}"#.to_string(),
+                ),
+            ]
+            .into(),
+        );
+        s.metadata.owner_references = Some(vec![OwnerReference {
+            kind: "ExternalSecret".to_string(),
+            name: "db".to_string(),
+            controller: Some(true),
+            ..Default::default()
+        }]);
+
+        assert_eq!(
+            metadata_lines(&s, 3 * 86400),
+            [
+                "Type:        Opaque",
+                "Age:         3d",
+                "Immutable:   true",
+                "Labels:      app=web",
+                "Annotations: kubectl.kubernetes.io/last-applied-configuration=<36 bytes>",
+                "             meta.helm.sh/release-name=web",
+                "Owners:      ExternalSecret/db (controller)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metadata_lines_without_metadata() {
+        let lines = metadata_lines(&secret("db", "Opaque", &[]), 0);
+        assert_eq!(lines[1], "Age:         <unknown>");
+        assert_eq!(lines[2], "Immutable:   false");
+        assert_eq!(
+            lines[3..],
+            [
+                "Labels:      <none>",
+                "Annotations: <none>",
+                "Owners:      <none>"
+            ]
+        );
+    }
+
+    fn live_secret() -> Secret {
+        let mut s = secret(
+            "db",
+            "Opaque",
+            &[("password", b"hunter2"), ("key.der", &[0x30, 0x82, 0xff])],
+        );
+        s.metadata.uid = Some("1234".to_string());
+        s.metadata.resource_version = Some("42".to_string());
+        s.metadata.creation_timestamp = Some(Time(k8s_openapi::jiff::Timestamp::UNIX_EPOCH));
+        s.metadata.managed_fields = Some(vec![Default::default()]);
+        s.metadata.labels = Some([("app".to_string(), "web".to_string())].into());
+        s.metadata.annotations = Some(
+            [(
+                LAST_APPLIED_ANNOTATION.to_string(),
+                r#"{"data":{"password":"aHVudGVyMg=="}}"#.to_string(),
+            )]
+            .into(),
+        );
+        s
+    }
+
+    #[test]
+    fn test_yaml_manifest_uses_string_data() {
+        let yaml = to_yaml(&[live_secret()], false).unwrap();
+        let manifest: serde_yaml::Value =
+            serde_yaml::from_str(yaml.trim_start_matches("---\n")).unwrap();
+        assert_eq!(manifest["apiVersion"], "v1");
+        assert_eq!(manifest["kind"], "Secret");
+        assert_eq!(manifest["type"], "Opaque");
+        assert_eq!(manifest["metadata"]["name"], "db");
+        assert_eq!(manifest["metadata"]["namespace"], "default");
+        assert_eq!(manifest["metadata"]["labels"]["app"], "web");
+        assert_eq!(manifest["stringData"]["password"], "hunter2");
+        assert_eq!(manifest["data"]["key.der"], "MIL/");
+        assert!(manifest["data"].get("password").is_none());
+    }
+
+    #[test]
+    fn test_yaml_manifest_strips_server_metadata() {
+        let yaml = to_yaml(&[live_secret()], false).unwrap();
+        assert!(!yaml.contains("uid"));
+        assert!(!yaml.contains("resourceVersion"));
+        assert!(!yaml.contains("managedFields"));
+        assert!(!yaml.contains("creationTimestamp"));
+        assert!(!yaml.contains("annotations"));
+        assert!(!yaml.contains("aHVudGVyMg=="));
+    }
+
+    #[test]
+    fn test_yaml_manifest_keeps_server_metadata() {
+        let yaml = to_yaml(&[live_secret()], true).unwrap();
+        assert!(yaml.contains("uid: '1234'"));
+        assert!(yaml.contains("resourceVersion: '42'"));
+        assert!(yaml.contains("managedFields"));
+        assert!(yaml.contains(LAST_APPLIED_ANNOTATION));
+    }
+
+    #[test]
+    fn test_masked_yaml_hides_last_applied_configuration() {
+        let yaml = to_yaml(&mask::secrets(&[live_secret()], None), true).unwrap();
+        assert!(yaml.contains("password: '****'"));
+        assert!(!yaml.contains(LAST_APPLIED_ANNOTATION));
+        assert!(!yaml.contains("aHVudGVyMg=="));
+    }
+
+    #[test]
+    fn test_yaml_separates_documents() {
+        let yaml = to_yaml(
+            &[secret("a", "Opaque", &[]), secret("b", "Opaque", &[])],
+            false,
+        )
+        .unwrap();
+        let documents: Vec<serde_yaml::Value> = serde_yaml::Deserializer::from_str(&yaml)
+            .map(serde_yaml::Value::deserialize)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1]["metadata"]["name"], "b");
+    }
+}
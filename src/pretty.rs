@@ -0,0 +1,298 @@
+//! Pretty printing values that hold JSON, YAML or more base64
+//!
+//! Detection is deliberately conservative, a value is only treated as
+//! structured when it parses as a JSON or YAML object or array, and as base64
+//! when it decodes to readable text. Anything else is printed as it is.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::Value;
+
+use crate::mask;
+
+/// Base64 values shorter than this are left alone, as short passwords
+/// decode to readable text too often
+const MIN_BASE64_LENGTH: usize = 16;
+
+/// A value rendered over several lines
+#[derive(Debug, PartialEq, Eq)]
+pub struct Pretty {
+    /// Whether the value had to be base64 decoded first
+    pub base64: bool,
+    pub lines: Vec<String>,
+}
+
+/// Render a structured value over several lines, `None` when it isn't one.
+/// Unless revealed, every scalar in JSON and YAML is masked but the keys and
+/// structure are kept, and decoded base64 text is masked entirely.
+pub fn render(value: &[u8], reveal: bool, peek: Option<usize>) -> Option<Pretty> {
+    let text = std::str::from_utf8(value).ok()?;
+    if let Some(lines) = structured(text, reveal, peek) {
+        return Some(Pretty {
+            base64: false,
+            lines,
+        });
+    }
+
+    let decoded = base64_text(text)?;
+    let lines = match structured(&decoded, reveal, peek) {
+        Some(lines) => lines,
+        None if reveal => decoded.lines().map(str::to_string).collect(),
+        None => vec![mask::mask(decoded.as_bytes(), peek)],
+    };
+    Some(Pretty {
+        base64: true,
+        lines,
+    })
+}
+
+/// Pretty print JSON, or indent YAML, when the text is an object or array
+fn structured(text: &str, reveal: bool, peek: Option<usize>) -> Option<Vec<String>> {
+    let trimmed = text.trim();
+    if trimmed.starts_with(['{', '['])
+        && let Ok(Value::Object(_) | Value::Array(_)) = serde_json::from_str(trimmed)
+    {
+        return Some(indent_json(trimmed, reveal, peek));
+    }
+
+    // Single lines like `user: admin` are valid YAML too, only take values
+    // that span several lines
+    if !trimmed.contains('\n') {
+        return None;
+    }
+    let yaml: serde_yaml::Value = serde_yaml::from_str(trimmed).ok()?;
+    if !yaml.is_mapping() && !yaml.is_sequence() {
+        return None;
+    }
+    if reveal {
+        return Some(trimmed.lines().map(str::to_string).collect());
+    }
+    let masked = serde_yaml::to_string(&mask::yaml(&yaml, peek)).ok()?;
+    Some(masked.lines().map(str::to_string).collect())
+}
+
+/// Indent valid JSON like `serde_json::to_string_pretty`, but copy keys and
+/// scalars as they're written rather than re-serializing them, so key order
+/// and numbers like `1.10` are shown as stored. Unless revealed, every value
+/// that isn't a key is masked, numbers and booleans included.
+fn indent_json(text: &str, reveal: bool, peek: Option<usize>) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let next_token = |from: usize| chars[from..].iter().find(|c| !c.is_whitespace()).copied();
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    };
+
+    let mut out = String::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '"' => {
+                let start = i - 1;
+                while chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i += 1;
+                let token: String = chars[start..i].iter().collect();
+                let is_key = next_token(i) == Some(':');
+                match serde_json::from_str::<String>(&token) {
+                    Ok(value) if !reveal && !is_key => {
+                        let masked = mask::mask(value.as_bytes(), peek);
+                        out.push_str(&serde_json::to_string(&masked).unwrap_or(token));
+                    }
+                    _ => out.push_str(&token),
+                }
+            }
+            '{' | '[' => {
+                out.push(c);
+                if matches!(next_token(i), Some('}' | ']')) {
+                    continue;
+                }
+                depth += 1;
+                newline(&mut out, depth);
+            }
+            '}' | ']' => {
+                if !out.ends_with(['{', '[']) {
+                    depth -= 1;
+                    newline(&mut out, depth);
+                }
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            _ => {
+                // A number, `true`, `false` or `null`
+                let start = i - 1;
+                while i < chars.len() && !chars[i].is_whitespace() && !",]}".contains(chars[i]) {
+                    i += 1;
+                }
+                let token: String = chars[start..i].iter().collect();
+                if reveal {
+                    out.push_str(&token);
+                } else {
+                    let masked = mask::mask(token.as_bytes(), peek);
+                    out.push_str(&serde_json::to_string(&masked).unwrap_or(token));
+                }
+            }
+        }
+    }
+    out.lines().map(str::to_string).collect()
+}
+
+/// Decode a value that is itself base64 encoded readable text
+fn base64_text(text: &str) -> Option<String> {
+    let trimmed = text.trim();
+    if trimmed.len() < MIN_BASE64_LENGTH {
+        return None;
+    }
+    let decoded = String::from_utf8(STANDARD.decode(trimmed).ok()?).ok()?;
+    let readable = decoded
+        .chars()
+        .all(|c| !c.is_control() || c == '\n' || c == '\r' || c == '\t');
+    readable.then_some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(value: &str, reveal: bool) -> Option<Vec<String>> {
+        render(value.as_bytes(), reveal, None).map(|p| p.lines)
+    }
+
+    #[test]
+    fn test_json() {
+        let value = r#"{"type":"service_account","project_id":"acme","port":5432}"#;
+        assert_eq!(
+            lines(value, true).unwrap(),
+            [
+                "{",
+                "  \"type\": \"service_account\",",
+                "  \"project_id\": \"acme\",",
+                "  \"port\": 5432",
+                "}",
+            ]
+        );
+        assert_eq!(
+            lines(value, false).unwrap()[2..4],
+            ["  \"project_id\": \"****\",", "  \"port\": \"****\""]
+        );
+    }
+
+    #[test]
+    fn test_json_masks_numbers_and_booleans() {
+        let value = r#"{"pin": 20240517, "admin": true, "ratio": -1.5e3, "next": null}"#;
+        assert_eq!(
+            lines(value, false).unwrap(),
+            [
+                "{",
+                "  \"pin\": \"****\",",
+                "  \"admin\": \"****\",",
+                "  \"ratio\": \"****\",",
+                "  \"next\": \"****\"",
+                "}",
+            ]
+        );
+        let peeked = render(value.as_bytes(), false, Some(2)).unwrap().lines;
+        assert_eq!(peeked[1], "  \"pin\": \"20****17 (8 chars)\",");
+    }
+
+    #[test]
+    fn test_json_is_shown_as_stored() {
+        let value =
+            r#"{"z": 1.10, "a": [12345678901234567890123, 1e3, {}, []], "s": "a\"b\u00e9"}"#;
+        assert_eq!(
+            lines(value, true).unwrap(),
+            [
+                "{",
+                "  \"z\": 1.10,",
+                "  \"a\": [",
+                "    12345678901234567890123,",
+                "    1e3,",
+                "    {},",
+                "    []",
+                "  ],",
+                "  \"s\": \"a\\\"b\\u00e9\"",
+                "}",
+            ]
+        );
+        let masked = lines(value, false).unwrap();
+        assert_eq!(masked[1], "  \"z\": \"****\",");
+        assert_eq!(masked[3..5], ["    \"****\",", "    \"****\","]);
+        assert_eq!(masked[8], "  \"s\": \"****\"");
+    }
+
+    #[test]
+    fn test_yaml() {
+        let value = "db:\n  user: app\n  password: hunter2\n";
+        assert_eq!(
+            lines(value, true).unwrap(),
+            ["db:", "  user: app", "  password: hunter2"]
+        );
+        assert_eq!(
+            lines(value, false).unwrap(),
+            ["db:", "  user: '****'", "  password: '****'"]
+        );
+    }
+
+    #[test]
+    fn test_yaml_masks_numbers_and_booleans() {
+        let value = "db:\n  password: 20240517\n  ssl: true\n  port: 5432\n";
+        assert_eq!(
+            lines(value, false).unwrap(),
+            [
+                "db:",
+                "  password: '****'",
+                "  ssl: '****'",
+                "  port: '****'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_base64() {
+        // base64 of {"token":"abc123"}
+        let pretty = render(b"eyJ0b2tlbiI6ImFiYzEyMyJ9", true, None).unwrap();
+        assert!(pretty.base64);
+        assert_eq!(pretty.lines, ["{", "  \"token\": \"abc123\"", "}"]);
+
+        // base64 of {"pin":1234,"admin":false}
+        let value = "eyJwaW4iOjEyMzQsImFkbWluIjpmYWxzZX0=";
+        assert_eq!(
+            lines(value, false).unwrap(),
+            ["{", "  \"pin\": \"****\",", "  \"admin\": \"****\"", "}"]
+        );
+
+        // base64 of "postgres://app:hunter2@db/app"
+        let value = "cG9zdGdyZXM6Ly9hcHA6aHVudGVyMkBkYi9hcHA=";
+        assert_eq!(
+            lines(value, true).unwrap(),
+            ["postgres://app:hunter2@db/app"]
+        );
+        assert_eq!(lines(value, false).unwrap(), ["****"]);
+    }
+
+    #[test]
+    fn test_plain_values_are_left_alone() {
+        for value in [
+            "hunter2",
+            "user: admin",
+            "42",
+            "\"quoted\"",
+            "[not json",
+            "just some\ntext",
+            "c2hvcnQ=",
+            // random bytes, not readable once decoded
+            "3q2+7wAAAAD/////AAAAAA==",
+        ] {
+            assert!(lines(value, true).is_none(), "{:?}", value);
+        }
+    }
+}
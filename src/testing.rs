@@ -0,0 +1,49 @@
+//! Fixtures shared by the unit tests
+
+use k8s_openapi::ByteString;
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+use crate::compare::Source;
+
+pub const RSA_CERT: &str = include_str!("../tests/fixtures/tls/rsa.crt");
+pub const RSA_KEY: &str = include_str!("../tests/fixtures/tls/rsa.key");
+pub const OTHER_RSA_KEY: &str = include_str!("../tests/fixtures/tls/rsa-other.key");
+pub const EC_CERT: &str = include_str!("../tests/fixtures/tls/ec.crt");
+pub const EC_KEY: &str = include_str!("../tests/fixtures/tls/ec.key");
+pub const EC_PKCS8_KEY: &str = include_str!("../tests/fixtures/tls/ec-pkcs8.key");
+
+/// When the fixture certificates were issued, 2026-10-16T20:51:43Z
+pub const ISSUED: i64 = 1792183903;
+
+/// Helper to create a test Secret in the `default` namespace
+pub fn secret(name: &str, type_: &str, data: &[(&str, &[u8])]) -> Secret {
+    Secret {
+        metadata: ObjectMeta {
+            name: Some(name.to_string()),
+            namespace: Some("default".to_string()),
+            ..Default::default()
+        },
+        type_: Some(type_.to_string()),
+        data: Some(
+            data.iter()
+                .map(|(k, v)| (k.to_string(), ByteString(v.to_vec())))
+                .collect(),
+        ),
+        ..Default::default()
+    }
+}
+
+/// Helper to build the indexed secrets `diff` and `--contexts` compare
+pub fn source(secrets: &[(&str, &[(&str, &str)])]) -> Source {
+    secrets
+        .iter()
+        .map(|(name, data)| {
+            let data = data
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.as_bytes().to_vec()))
+                .collect();
+            (name.to_string(), data)
+        })
+        .collect()
+}
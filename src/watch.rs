@@ -0,0 +1,290 @@
+//! Watching secrets and printing changes to them as they happen
+//!
+//! Changes are worked out by keeping the last seen data of every selected
+//! secret and diffing each event against it, so the output looks just like
+//! `secrets diff`. When the watch is restarted after an error the secrets are
+//! listed again and anything that changed in the meantime is reported too.
+//!
+//! Like the listing, a list of namespaces or patterns is resolved up front and
+//! each namespace gets its own watch, so only `--all-namespaces` needs to
+//! watch secrets across the cluster.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::ExitCode;
+
+use anyhow::Context;
+use futures::StreamExt;
+use k8s_openapi::api::core::v1::Secret;
+use kube::{Api, Client};
+use kube_runtime::WatchStreamExt;
+use kube_runtime::watcher::{self, Event};
+
+use crate::compare::{self, Source};
+use crate::diff::{self, SecretDiff};
+use crate::filter::{self, Filter};
+use crate::{Config, namespaces};
+
+/// Watch the selected secrets until interrupted
+///
+/// Selectors are applied by the API server, the rest of the filters are
+/// applied to every event. Secrets that stop matching them are reported as
+/// removed. Errors that retrying won't fix, like not being allowed to watch
+/// secrets, end the watch.
+pub async fn run(config: &Config, filter: &Filter, client: Client) -> anyhow::Result<ExitCode> {
+    if config.output.is_structured() {
+        anyhow::bail!("--watch only supports text output");
+    }
+
+    let namespace = config.namespace.as_deref().unwrap_or_default();
+    let (apis, description): (Vec<Api<Secret>>, _) = if config.all_namespaces {
+        (vec![Api::all(client)], "every namespace".to_string())
+    } else if namespaces::is_pattern(namespace) {
+        let names = namespaces::resolve(client.clone(), namespace).await?;
+        if names.is_empty() {
+            anyhow::bail!("No namespaces match '{}'", namespace);
+        }
+        (
+            names
+                .iter()
+                .map(|name| Api::namespaced(client.clone(), name))
+                .collect(),
+            format!("namespaces {}", names.join(", ")),
+        )
+    } else {
+        crate::ensure_namespace(client.clone(), namespace).await?;
+        (
+            vec![Api::namespaced(client, namespace)],
+            format!("namespace '{}'", namespace),
+        )
+    };
+
+    println!("Watching secrets in {}, press Ctrl-C to stop", description);
+    // Each watch lists its namespace again after reconnecting, so each needs
+    // its own tracker to tell which secrets went missing
+    let mut trackers: Vec<Tracker> = apis.iter().map(|_| Tracker::default()).collect();
+    let mut events = futures::stream::select_all(apis.into_iter().enumerate().map(|(i, api)| {
+        watcher::watcher(api, filter.watcher_config())
+            .default_backoff()
+            .map(move |event| (i, event))
+            .boxed()
+    }));
+    while let Some((i, event)) = events.next().await {
+        let event = match event {
+            Ok(event) => event.modify(|s| filter.retain_matching_keys(s)),
+            Err(e) if is_permanent(&e) => {
+                return Err(e)
+                    .with_context(|| format!("Unable to watch secrets in {}", description));
+            }
+            Err(e) => {
+                eprintln!("Watch failed, reconnecting: {}", e);
+                continue;
+            }
+        };
+        // A secret that no longer matches the filters is as good as deleted
+        let event = match event {
+            Event::Apply(s) | Event::InitApply(s) if !filter::display_secret(filter, &s) => {
+                Event::Delete(s)
+            }
+            event => event,
+        };
+        print!(
+            "{}",
+            diff::render(&trackers[i].handle(event), config.reveal)
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Whether the API server refused the watch in a way reconnecting won't fix,
+/// such as missing RBAC permissions
+fn is_permanent(e: &watcher::Error) -> bool {
+    let status = match e {
+        watcher::Error::InitialListFailed(kube::Error::Api(status))
+        | watcher::Error::WatchStartFailed(kube::Error::Api(status))
+        | watcher::Error::WatchFailed(kube::Error::Api(status))
+        | watcher::Error::WatchError(status) => status,
+        _ => return false,
+    };
+    matches!(status.code, 401 | 403 | 404)
+}
+
+/// The last seen data of every selected secret
+#[derive(Default)]
+struct Tracker {
+    known: Source,
+    /// Secrets listed so far while the watch is (re)starting
+    listed: BTreeSet<String>,
+    /// Whether the initial listing is done, before which nothing is reported
+    synced: bool,
+}
+
+impl Tracker {
+    /// Record the event, returning how it changed the secrets
+    fn handle(&mut self, event: Event<Secret>) -> Vec<SecretDiff> {
+        match event {
+            Event::Init => {
+                self.listed.clear();
+                Vec::new()
+            }
+            Event::InitApply(s) => {
+                let (id, data) = entry(&s);
+                self.listed.insert(id.clone());
+                let diffs = self.update(&id, Some(data));
+                if self.synced { diffs } else { Vec::new() }
+            }
+            Event::InitDone => {
+                // Anything not listed again was deleted while we weren't watching
+                let gone: Vec<String> = self
+                    .known
+                    .keys()
+                    .filter(|id| !self.listed.contains(*id))
+                    .cloned()
+                    .collect();
+                let diffs = gone.iter().flat_map(|id| self.update(id, None)).collect();
+                self.synced = true;
+                diffs
+            }
+            Event::Apply(s) => {
+                let (id, data) = entry(&s);
+                let diffs = self.update(&id, Some(data));
+                if diffs.is_empty() {
+                    // Written without changing any values, still worth
+                    // knowing when chasing down what keeps updating it
+                    vec![SecretDiff::Changed(id, Vec::new())]
+                } else {
+                    diffs
+                }
+            }
+            Event::Delete(s) => self.update(&entry(&s).0, None),
+        }
+    }
+
+    /// Replace the data of a secret, or forget it, returning the difference
+    fn update(&mut self, id: &str, data: Option<BTreeMap<String, Vec<u8>>>) -> Vec<SecretDiff> {
+        let old: Source = self
+            .known
+            .remove(id)
+            .map(|d| (id.to_string(), d))
+            .into_iter()
+            .collect();
+        let new: Source = data.map(|d| (id.to_string(), d)).into_iter().collect();
+        let diffs = diff::diff(&old, &new);
+        self.known.extend(new);
+        diffs
+    }
+}
+
+/// The `namespace/name` and data of a secret
+fn entry(s: &Secret) -> (String, BTreeMap<String, Vec<u8>>) {
+    compare::index(std::slice::from_ref(s), true)
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::KeyDiff;
+    use crate::testing::secret;
+
+    fn synced(secrets: &[Secret]) -> Tracker {
+        let mut tracker = Tracker::default();
+        tracker.handle(Event::Init);
+        for s in secrets {
+            assert!(tracker.handle(Event::InitApply(s.clone())).is_empty());
+        }
+        assert!(tracker.handle(Event::InitDone).is_empty());
+        tracker
+    }
+
+    #[test]
+    fn test_added_modified_deleted() {
+        let mut tracker = synced(&[secret("db", "Opaque", &[("password", b"a")])]);
+
+        let diffs = tracker.handle(Event::Apply(secret("api", "Opaque", &[("token", b"x")])));
+        assert_eq!(diffs, [SecretDiff::Added("default/api".to_string())]);
+
+        let diffs = tracker.handle(Event::Apply(secret(
+            "db",
+            "Opaque",
+            &[("password", b"b"), ("user", b"app")],
+        )));
+        assert_eq!(
+            diffs,
+            [SecretDiff::Changed(
+                "default/db".to_string(),
+                vec![
+                    KeyDiff::Changed("password".to_string(), b"a".to_vec(), b"b".to_vec()),
+                    KeyDiff::Added("user".to_string(), b"app".to_vec()),
+                ]
+            )]
+        );
+
+        let diffs = tracker.handle(Event::Delete(secret("api", "Opaque", &[])));
+        assert_eq!(diffs, [SecretDiff::Removed("default/api".to_string())]);
+    }
+
+    #[test]
+    fn test_write_without_changes() {
+        let mut tracker = synced(&[secret("db", "Opaque", &[("password", b"a")])]);
+        let diffs = tracker.handle(Event::Apply(secret("db", "Opaque", &[("password", b"a")])));
+        assert_eq!(
+            diffs,
+            [SecretDiff::Changed("default/db".to_string(), Vec::new())]
+        );
+    }
+
+    #[test]
+    fn test_delete_unknown_secret() {
+        let mut tracker = synced(&[]);
+        assert!(
+            tracker
+                .handle(Event::Delete(secret("tls", "Opaque", &[])))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_is_permanent() {
+        let status = |code| kube::core::Status::failure("denied", "Forbidden").with_code(code);
+        assert!(is_permanent(&watcher::Error::InitialListFailed(
+            kube::Error::Api(status(403).boxed())
+        )));
+        assert!(is_permanent(&watcher::Error::WatchError(
+            status(401).boxed()
+        )));
+        assert!(!is_permanent(&watcher::Error::WatchError(
+            status(410).boxed()
+        )));
+        assert!(!is_permanent(&watcher::Error::NoResourceVersion));
+    }
+
+    #[test]
+    fn test_relist_reports_missed_changes() {
+        let mut tracker = synced(&[
+            secret("db", "Opaque", &[("password", b"a")]),
+            secret("api", "Opaque", &[("token", b"x")]),
+        ]);
+
+        tracker.handle(Event::Init);
+        let diffs = tracker.handle(Event::InitApply(secret(
+            "db",
+            "Opaque",
+            &[("password", b"b")],
+        )));
+        assert_eq!(
+            diffs,
+            [SecretDiff::Changed(
+                "default/db".to_string(),
+                vec![KeyDiff::Changed(
+                    "password".to_string(),
+                    b"a".to_vec(),
+                    b"b".to_vec()
+                )]
+            )]
+        );
+        let diffs = tracker.handle(Event::InitDone);
+        assert_eq!(diffs, [SecretDiff::Removed("default/api".to_string())]);
+    }
+}
@@ -1,9 +1,9 @@
-/// Integration tests for the CLI interface
-///
-/// These tests verify the actual binary behavior including:
-/// - Help output
-/// - Version output
-/// - Error messages for invalid arguments
+//! Integration tests for the CLI interface
+//!
+//! These tests verify the actual binary behavior including:
+//! - Help output
+//! - Version output
+//! - Error messages for invalid arguments
 
 use std::process::Command;
 
@@ -29,7 +29,9 @@ fn test_help_contains_usage() {
     assert!(help_output.contains("Arguments:"), "Help should contain Arguments section");
     assert!(help_output.contains("Options:"), "Help should contain Options section");
     assert!(help_output.contains("-a, --show-all"), "Help should show show-all option");
-    assert!(help_output.contains("<NAMESPACE>"), "Help should show NAMESPACE argument");
+    // NAMESPACE is optional in the usage line since --all-namespaces can replace it
+    assert!(help_output.contains("[NAMESPACE]"), "Help should show NAMESPACE argument");
+    assert!(help_output.contains("-A, --all-namespaces"), "Help should show all-namespaces option");
     assert!(help_output.contains("[QUERY]"), "Help should show query argument");
     assert!(help_output.contains("-h, --help"), "Help should show help option");
     assert!(help_output.contains("-V, --version"), "Help should show version option");
@@ -310,3 +312,182 @@ fn test_version_flag_short_and_long() {
         assert!(version_output.contains(BINARY_NAME), "{} should show binary name", flag);
     }
 }
+
+#[test]
+fn test_help_lists_output_option() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--help"])
+        .output()
+        .expect("Failed to execute command with --help");
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stdout.contains("-o, --output"),
+        "Help should show output option"
+    );
+    assert!(stdout.contains("json"), "Help should list the json format");
+}
+
+#[test]
+fn test_invalid_output_format_shows_error() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "-o", "xml", "default"])
+        .output()
+        .expect("Failed to execute command with invalid output format");
+
+    assert!(
+        !output.status.success(),
+        "Unknown output format should fail"
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("xml"),
+        "Error should mention the rejected value"
+    );
+}
+
+#[test]
+fn test_exec_requires_command() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "exec", "default", "app"])
+        .output()
+        .expect("Failed to execute exec without a command");
+
+    assert!(
+        !output.status.success(),
+        "exec without a command should fail"
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("<COMMAND>"),
+        "Error should mention the missing command"
+    );
+}
+
+#[test]
+fn test_invalid_regex_reported_before_connecting() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--regex", "default", "app-("])
+        .output()
+        .expect("Failed to execute command with invalid regex");
+
+    assert!(!output.status.success(), "Invalid regex should fail");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid regular expression 'app-('"),
+        "Error should name the invalid regex"
+    );
+}
+
+#[test]
+fn test_reveal_from_environment() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--regex", "default", "app-("])
+        .env("KUBE_SECRETS_REVEAL", "1")
+        .output()
+        .expect("Failed to execute command with KUBE_SECRETS_REVEAL set");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid regular expression"),
+        "KUBE_SECRETS_REVEAL=1 should be accepted"
+    );
+}
+
+#[test]
+fn test_errors_exit_with_2() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "diff",
+            "app-stage",
+            "app-prod",
+            "--kubeconfig",
+            "/nonexistent/kubeconfig",
+        ])
+        .output()
+        .expect("Failed to execute diff with a missing kubeconfig");
+
+    assert_eq!(output.status.code(), Some(2), "Errors should exit with 2");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unable to read kubeconfig"),
+        "Error should be printed"
+    );
+}
+
+#[test]
+fn test_masked_manifest_refused_when_redirected() {
+    for format in ["yaml", "json"] {
+        let output = Command::new("cargo")
+            .args(["run", "--", "-o", format, "default"])
+            .output()
+            .expect("Failed to execute command with -o");
+
+        assert!(
+            !output.status.success(),
+            "Masked {format} to a pipe should fail"
+        );
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("--reveal"), "Error should suggest --reveal");
+    }
+}
+
+#[test]
+fn test_certs_errors_exit_with_2() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "certs",
+            "default",
+            "--kubeconfig",
+            "/nonexistent/kubeconfig",
+        ])
+        .output()
+        .expect("Failed to execute certs with a missing kubeconfig");
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "Errors should exit with 2 rather than the 1 for expiring certificates"
+    );
+}
+
+#[test]
+fn test_contexts_rejects_unsupported_output() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--contexts",
+            "us,eu",
+            "-o",
+            "yaml",
+            "--reveal",
+            "default",
+        ])
+        .output()
+        .expect("Failed to execute --contexts with -o yaml");
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "Unsupported output should fail"
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--contexts only supports text and JSON output"),
+        "Error should name the supported formats"
+    );
+}